@@ -1,9 +1,12 @@
 #![feature(mixed_integer_ops)]
 #![feature(bool_to_option)]
 
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::iter::{from_fn};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
 
 const FILENAME: &'static str = "i1.txt";
 
@@ -21,15 +24,19 @@ fn read_lines() -> impl Iterator<Item = String> {
 }
 
 fn read_data() -> Vec<Vec<u8>> {
-    read_lines().map(|v| v.chars().filter_map(|c| {
+    let data = read_lines().map(|v| v.chars().filter_map(|c| {
         Some(match c {
             '0'..='9' => (c as u8) - b'0',
             _ => return None
         })
-    }).collect()).collect()
+    }).collect::<Vec<_>>()).filter(|v| !v.is_empty()).collect::<Vec<_>>();
+    if data.iter().any(|v| v.len() != data[0].len()) {
+        panic!("grid is not rectangular")
+    }
+    data
 }
 
-fn get_adjacent(y: usize, x: usize) -> impl Iterator<Item=(usize, usize)> {
+fn get_adjacent(y: usize, x: usize, height: usize, width: usize) -> impl Iterator<Item=(usize, usize)> {
     (-1..=1).into_iter().map(|y_off| {
         (-1..=1).into_iter().map(move |x_off| (y_off, x_off))
     })
@@ -40,64 +47,170 @@ fn get_adjacent(y: usize, x: usize) -> impl Iterator<Item=(usize, usize)> {
                 x.checked_add_signed(v.1)?
             ))
         })
-        .filter(|v| (v.0 < 10) && (v.1 < 10))
+        .filter(move |v| (v.0 < height) && (v.1 < width))
 }
 
-fn tick(data: &mut Vec<Vec<u8>>) -> u32 {
-    fn inc_pos(data: &mut Vec<Vec<u8>>, y: usize, x: usize) {
-        match data[y][x] {
-            0..=8 => data[y][x] += 1,
-            9 => {
-                data[y][x] = 10;
-                get_adjacent(y, x).for_each(|v| inc_pos(data, v.0, v.1))
-            },
-            _ => {}
+// the octopuses which flashed, in the order they flashed
+fn tick(data: &mut Vec<Vec<u8>>) -> Vec<(usize, usize)> {
+    let (height, width) = (data.len(), data[0].len());
+    let mut flashed = Vec::new();
+    // octopuses still to be given energy, by the step or a neighbouring flash
+    let mut work = VecDeque::new();
+    for y in 0..height {
+        for x in 0..width {
+            work.push_back((y, x));
+            while let Some((y, x)) = work.pop_front() {
+                match data[y][x] {
+                    0..=8 => data[y][x] += 1,
+                    9 => {
+                        data[y][x] = 10;
+                        flashed.push((y, x));
+                        work.extend(get_adjacent(y, x, height, width))
+                    },
+                    _ => {}
+                }
+            }
         }
     }
 
-    for y in 0..10 {
-        for x in 0..10 {
-            inc_pos(data, y, x)
+    for &(y, x) in flashed.iter() {
+        data[y][x] = 0;
+    }
+    flashed
+}
+
+// the step the state first repeats from and the period, as (start, period)
+fn find_cycle(mut data: Vec<Vec<u8>>, limit: usize) -> Option<(usize, usize)> {
+    let mut seen = HashMap::new();
+    seen.insert(data.clone(), 0);
+    for pos in 1..=limit {
+        tick(&mut data);
+        if let Some(prev) = seen.insert(data.clone(), pos) {
+            return Some((prev, pos - prev))
         }
     }
+    None
+}
+
+// the input repeated to fill a grid of the given size
+fn tile(data: &Vec<Vec<u8>>, height: usize, width: usize) -> Vec<Vec<u8>> {
+    (0..height)
+        .map(|y| (0..width).map(|x| data[y % data.len()][x % data[0].len()]).collect())
+        .collect()
+}
+
+fn bench(data: &Vec<Vec<u8>>, steps: usize) {
+    let mut big = tile(data, 1000, 1000);
+    let start = Instant::now();
+    let flashes = (0..steps).map(|_| tick(&mut big).len()).sum::<usize>();
+    let took = start.elapsed();
+    println!(
+        "1000x1000: {} steps, {} flashes in {:?} ({:?} per step)",
+        steps, flashes, took, took / steps as u32
+    );
+}
+
+// the state after each step is only copied out when keep_state is set
+fn tick_it(mut data: Vec<Vec<u8>>, keep_state: bool) -> impl Iterator<Item=(usize, Vec<(usize, usize)>, Option<Vec<Vec<u8>>>)> {
+    from_fn(move || {
+        let flashed = tick(&mut data);
+        Some((flashed, keep_state.then(|| data.clone())))
+    }).enumerate().map(|(i, (flashed, data))| (i + 1, flashed, data))
+}
 
-    let mut ret = 0;
-    for y in 0..10 {
-        for x in 0..10 {
-            if data[y][x] == 10 {
-                ret += 1;
-                data[y][x] = 0;
+// octopuses which just flashed are shown as *, or highlighted when using ANSI escapes
+fn render(data: &Vec<Vec<u8>>, ansi: bool) -> String {
+    let mut ret = String::new();
+    for row in data {
+        for &v in row {
+            match (v, ansi) {
+                (0, true) => ret.push_str("\x1b[1;93m0\x1b[0m"),
+                (0, false) => ret.push('*'),
+                (v, _) => ret.push((b'0' + v) as char)
             }
         }
+        ret.push('\n');
     }
     ret
 }
 
-fn tick_it(mut data: Vec<Vec<u8>>) -> impl Iterator<Item=(usize, u32)> {
-    from_fn(move || Some(tick(&mut data))).enumerate().map(|v| (v.0 + 1, v.1))
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
 }
 
 fn main() {
     let data = read_data();
+    if std::env::args().any(|a| a == "--bench") {
+        bench(&data, arg_value("--steps").map_or(100, |v| v.parse().unwrap()));
+        return
+    }
+    let size = data.len() * data[0].len();
+
+    let trace = std::env::args().any(|a| a == "--trace");
+    let animate = std::env::args().any(|a| a == "--animate");
+    let ansi = !std::env::args().any(|a| a == "--text");
+    let delay = Duration::from_millis(arg_value("--delay").map_or(100, |v| v.parse().unwrap()));
+    let steps = arg_value("--steps").map_or(100, |v| v.parse().unwrap());
+    // look for repeating states, which show a synchronised flash never comes
+    let detect_cycle = std::env::args().any(|a| a == "--cycle");
+    // give up looking for a synchronised flash after this many steps
+    let limit = arg_value("--limit").map_or(1_000_000, |v| v.parse().unwrap());
 
+    if animate {
+        print!("{}{}step 0\n", if ansi { "\x1b[H\x1b[2J" } else { "" }, render(&data, ansi));
+    }
     let mut res_1 = 0;
     let mut res_2 = 0;
-    for (pos, n) in tick_it(data) {
-        let mut did_op = false;
+    // states seen while still waiting for a synchronised flash, a repeat means it never comes
+    let mut seen = HashMap::new();
+    let mut cycle = None;
+    if detect_cycle {
+        seen.insert(data.clone(), 0);
+    }
+    for (pos, flashed, state) in tick_it(data.clone(), animate || detect_cycle) {
+        if pos <= steps {
+            if trace {
+                let order = flashed.iter().map(|(y, x)| format!("{},{}", y, x)).collect::<Vec<_>>();
+                println!("step {}: {}", pos, order.join(" "));
+            }
+            if animate {
+                if ansi {
+                    print!("\x1b[H\x1b[2J")
+                } else {
+                    println!()
+                }
+                print!("{}step {}: {} flashed\n", render(state.as_ref().unwrap(), ansi), pos, flashed.len());
+                sleep(delay);
+            }
+        }
         if pos <= 100 {
-            did_op = true;
-            res_1 += n;
+            res_1 += flashed.len();
+        }
+        if res_2 == 0 && flashed.len() == size {
+            res_2 = pos;
         }
-        if res_2 == 0 {
-            did_op = true;
-            if n == 100 {
-                res_2 = pos;
+        if detect_cycle && res_2 == 0 && cycle.is_none() {
+            if let Some(prev) = seen.insert(state.unwrap(), pos) {
+                cycle = Some((prev, pos - prev))
             }
         }
-        if !did_op {
+        if (pos >= 100 && pos >= steps && (res_2 != 0 || cycle.is_some())) || pos >= limit {
             break
         }
     }
     println!("1> {}", res_1);
-    println!("2> {}", res_2);
+    if res_2 != 0 {
+        println!("2> {}", res_2);
+    }
+    if res_2 != 0 && detect_cycle {
+        cycle = find_cycle(data, limit)
+    }
+    match (cycle, res_2) {
+        (Some((start, period)), 0) => println!("2> never, states repeat from step {} with period {}", start, period),
+        (None, 0) => println!("2> no synchronised flash within {} steps", limit),
+        (Some((start, period)), _) => println!("cycle> states repeat from step {} with period {}", start, period),
+        (None, _) => {}
+    }
 }
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use crate::CaveGraph;
+
+// each undirected edge once, as it appears in the input
+fn undirected(graph: &CaveGraph) -> impl '_ + Iterator<Item=(usize, usize)> {
+    graph.edges.iter().step_by(2).copied()
+}
+
+// which steps of the path cross each undirected edge
+fn path_steps(graph: &CaveGraph, path: &[&str]) -> Result<HashMap<(usize, usize), Vec<usize>>> {
+    let ids = path
+        .iter()
+        .map(|name| graph.names
+            .iter()
+            .position(|n| n == name)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("no cave named {}", name))))
+        .collect::<Result<Vec<_>>>()?;
+    let mut ret = HashMap::new();
+    for (step, w) in ids.windows(2).enumerate() {
+        if !graph.adj[w[0]].contains(&w[1]) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("no passage from {} to {}", graph.name(w[0]), graph.name(w[1]))
+            ))
+        }
+        ret.entry((w[0].min(w[1]), w[0].max(w[1]))).or_insert_with(Vec::new).push(step + 1);
+    }
+    Ok(ret)
+}
+
+pub(crate) fn write_dot(path: &str, graph: &CaveGraph, overlay: Option<&[&str]>) -> Result<()> {
+    let steps = match overlay {
+        Some(p) => path_steps(graph, p)?,
+        None => HashMap::new()
+    };
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(w, "graph caves {{")?;
+    for (id, name) in graph.names.iter().enumerate() {
+        let style = if id == graph.start {
+            "shape=doublecircle, style=filled, fillcolor=palegreen"
+        } else if id == graph.end {
+            "shape=doublecircle, style=filled, fillcolor=salmon"
+        } else if graph.big[id] {
+            "shape=box, style=filled, fillcolor=lightblue"
+        } else {
+            "shape=ellipse"
+        };
+        writeln!(w, "    {:?} [{}];", name, style)?;
+    }
+    for (a, b) in undirected(graph) {
+        match steps.get(&(a.min(b), a.max(b))) {
+            Some(s) => {
+                let label = s.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(",");
+                writeln!(
+                    w, "    {:?} -- {:?} [color=red, penwidth=2, label={:?}];",
+                    graph.name(a), graph.name(b), label
+                )?
+            }
+            None => writeln!(w, "    {:?} -- {:?};", graph.name(a), graph.name(b))?
+        }
+    }
+    writeln!(w, "}}")?;
+    w.flush()
+}
+
+pub(crate) fn write_json(path: &str, graph: &CaveGraph, overlay: Option<&[&str]>) -> Result<()> {
+    let steps = match overlay {
+        Some(p) => path_steps(graph, p)?,
+        None => HashMap::new()
+    };
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(w, "{{")?;
+    writeln!(w, "  \"start\": {:?},", graph.name(graph.start))?;
+    writeln!(w, "  \"end\": {:?},", graph.name(graph.end))?;
+    writeln!(w, "  \"caves\": {{")?;
+    for (id, name) in graph.names.iter().enumerate() {
+        let adj = graph.adj[id]
+            .iter()
+            .map(|&to| format!("{:?}", graph.name(to)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            w, "    {:?}: {{\"big\": {}, \"adjacent\": [{}]}}{}",
+            name, graph.big[id], adj,
+            if id + 1 == graph.names.len() { "" } else { "," }
+        )?;
+    }
+    writeln!(w, "  }}{}", if overlay.is_some() { "," } else { "" })?;
+    if let Some(p) = overlay {
+        let names = p.iter().map(|n| format!("{:?}", n)).collect::<Vec<_>>().join(", ");
+        writeln!(w, "  \"path\": [{}],", names)?;
+        let mut edges = steps.keys().copied().collect::<Vec<_>>();
+        edges.sort();
+        let edges = edges
+            .iter()
+            .map(|&(a, b)| format!("[{:?}, {:?}]", graph.name(a), graph.name(b)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(w, "  \"path_edges\": [{}]", edges)?;
+    }
+    writeln!(w, "}}")?;
+    w.flush()
+}
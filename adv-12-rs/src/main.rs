@@ -1,6 +1,10 @@
 #![feature(mixed_integer_ops)]
 #![feature(bool_to_option)]
 
+mod paths;
+mod policy;
+mod export;
+
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
@@ -8,6 +12,7 @@ use std::collections::hash_map::Entry;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::iter::{from_fn};
+use crate::policy::VisitPolicy;
 
 const FILENAME: &'static str = "i1.txt";
 
@@ -24,9 +29,27 @@ fn read_lines() -> impl Iterator<Item = String> {
     })
 }
 
-fn read_data() -> (usize, usize, Vec<(usize, usize)>, Vec<bool>) {
+struct CaveGraph {
+    names: Vec<String>,
+    // big caves may be visited any number of times
+    big: Vec<bool>,
+    adj: Vec<Vec<usize>>,
+    // both directions of every edge
+    edges: Vec<(usize, usize)>,
+    start: usize,
+    end: usize
+}
+
+impl CaveGraph {
+    fn name(&self, id: usize) -> &str {
+        self.names[id].as_str()
+    }
+}
+
+fn read_data() -> CaveGraph {
     let mut ret = Vec::new();
     let mut can_multi = Vec::new();
+    let mut names = Vec::new();
     let mut id_map = HashMap::new();
     let mut next_id = 0;
     for line in read_lines() {
@@ -38,23 +61,31 @@ fn read_data() -> (usize, usize, Vec<(usize, usize)>, Vec<bool>) {
             let n = next_id;
             next_id += 1;
             can_multi.push(a.chars().next().unwrap().is_uppercase());
+            names.push(a.clone());
             n
         });
         let b_id = *id_map.entry(b).or_insert_with_key(|b| {
             let n = next_id;
             next_id += 1;
             can_multi.push(b.chars().next().unwrap().is_uppercase());
+            names.push(b.clone());
             n
         });
         ret.push((a_id, b_id));
         ret.push((b_id, a_id));
     }
-    (
-        *id_map.get("start").unwrap(),
-        *id_map.get("end").unwrap(),
-        ret,
-        can_multi
-    )
+    let mut adj = vec![Vec::new(); next_id];
+    for &(a, b) in ret.iter() {
+        adj[a].push(b)
+    }
+    CaveGraph {
+        names,
+        big: can_multi,
+        adj,
+        edges: ret,
+        start: *id_map.get("start").unwrap(),
+        end: *id_map.get("end").unwrap()
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -168,9 +199,82 @@ fn find_path_cnt(
     sum
 }
 
+fn arg_values<'a>(args: &'a [String], name: &'a str) -> impl 'a + Iterator<Item=&'a str> {
+    args
+        .windows(2)
+        .filter(move |w| w[0] == name)
+        .map(|w| w[1].as_str())
+}
+
+// part 2 rules unless overridden by --extra k:m, --limit cave=n or --forbid cave
+fn custom_policy(args: &[String]) -> Option<VisitPolicy> {
+    let mut policy = VisitPolicy::part2();
+    let mut custom = false;
+    for v in arg_values(args, "--extra") {
+        let (k, m) = v.split_once(':').expect("expected --extra caves:visits");
+        policy.extra_caves = k.parse().unwrap();
+        policy.extra_limit = m.parse().unwrap();
+        custom = true;
+    }
+    for v in arg_values(args, "--limit") {
+        let (cave, n) = v.split_once('=').expect("expected --limit cave=visits");
+        policy.limits.insert(String::from(cave), n.parse().unwrap());
+        custom = true;
+    }
+    for v in arg_values(args, "--forbid") {
+        policy.forbidden.insert(String::from(v));
+        custom = true;
+    }
+    custom.then_some(policy)
+}
+
 fn main() {
-    let (start, end, paths, can_multi) = read_data();
+    let graph = read_data();
+    let args = std::env::args().collect::<Vec<_>>();
+    let policy = custom_policy(args.as_slice());
+
+    let overlay = arg_values(args.as_slice(), "--path")
+        .next()
+        .map(|p| p.split(',').collect::<Vec<_>>());
+    if let Some(path) = arg_values(args.as_slice(), "--dot").next() {
+        export::write_dot(path, &graph, overlay.as_deref()).unwrap();
+    }
+    if let Some(path) = arg_values(args.as_slice(), "--json").next() {
+        export::write_json(path, &graph, overlay.as_deref()).unwrap();
+    }
+
+    if let Some(part) = arg_values(args.as_slice(), "--list").next() {
+        let policy = match (part, policy) {
+            ("1", _) => VisitPolicy::part1(),
+            ("2", _) => VisitPolicy::part2(),
+            (_, Some(p)) => p,
+            _ => panic!("expected --list 1, --list 2 or a custom policy")
+        };
+        for path in paths::PathIter::new(&graph, &policy) {
+            println!("{}", path.join(","));
+        }
+        return
+    }
+
+    if args.iter().any(|a| a == "--naive") {
+        let (start, end) = (graph.start, graph.end);
+        let (paths, can_multi) = (graph.edges.as_slice(), graph.big.as_slice());
+        println!("1> {}", find_path_cnt(start, end, paths, can_multi, VisitState::NoTwice, true));
+        println!("2> {}", find_path_cnt(start, end, paths, can_multi, VisitState::CanTwice, true));
+        return
+    }
+
+    if let Some(policy) = policy {
+        match paths::count_paths(&graph, &policy) {
+            Ok(n) => println!("custom> {}", n),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2)
+            }
+        }
+        return
+    }
 
-    println!("1> {}", find_path_cnt(start, end, paths.as_slice(), can_multi.as_slice(), VisitState::NoTwice, true));
-    println!("2> {}", find_path_cnt(start, end, paths.as_slice(), can_multi.as_slice(), VisitState::CanTwice, true));
+    println!("1> {}", paths::count_paths(&graph, &VisitPolicy::part1()).unwrap());
+    println!("2> {}", paths::count_paths(&graph, &VisitPolicy::part2()).unwrap());
 }
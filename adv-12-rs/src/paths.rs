@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use crate::CaveGraph;
+use crate::policy::{Rules, VisitPolicy};
+
+struct Frame {
+    cave: usize,
+    // index into the adjacency list of the next neighbour to try
+    next: usize
+}
+
+// depth first enumeration of every path from start to end allowed by a policy
+pub(crate) struct PathIter<'a> {
+    graph: &'a CaveGraph,
+    rules: Rules,
+    stack: Vec<Frame>,
+    visits: Vec<u32>,
+    extra: u32
+}
+
+impl<'a> PathIter<'a> {
+    pub(crate) fn new(graph: &'a CaveGraph, policy: &VisitPolicy) -> Self {
+        let mut visits = vec![0; graph.names.len()];
+        visits[graph.start] = 1;
+        PathIter {
+            graph,
+            rules: policy.resolve(graph),
+            stack: vec![Frame { cave: graph.start, next: 0 }],
+            visits,
+            extra: 0
+        }
+    }
+
+    fn enter(&mut self, cave: usize) {
+        if self.rules.becomes_extra(cave, self.visits[cave]) {
+            self.extra += 1
+        }
+        self.visits[cave] += 1;
+        self.stack.push(Frame { cave, next: 0 });
+    }
+
+    fn leave(&mut self) {
+        if let Some(frame) = self.stack.pop() {
+            self.visits[frame.cave] -= 1;
+            if self.rules.becomes_extra(frame.cave, self.visits[frame.cave]) {
+                self.extra -= 1
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for PathIter<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            let cave = frame.cave;
+            if cave == self.graph.end {
+                let graph = self.graph;
+                let path = self.stack
+                    .iter()
+                    .map(|f| graph.name(f.cave))
+                    .collect();
+                self.leave();
+                return Some(path)
+            }
+            match self.graph.adj[cave].get(frame.next) {
+                Some(&to) => {
+                    frame.next += 1;
+                    if self.rules.can_enter(to, self.visits[to], self.extra) {
+                        self.enter(to)
+                    }
+                }
+                None => self.leave()
+            }
+        }
+    }
+}
+
+// visits to every tracked cave packed into one integer, each cave getting just
+// enough bits for the most visits its rule allows
+struct VisitKey {
+    // bit offset of each tracked cave
+    shift: Vec<Option<u32>>,
+    mask: Vec<u128>
+}
+
+impl VisitKey {
+    fn new(graph: &CaveGraph, rules: &Rules) -> Result<Self, String> {
+        let mut shift = Vec::new();
+        let mut mask = Vec::new();
+        let mut used = 0;
+        for cave in 0..graph.names.len() {
+            let bits = rules.max_visits(cave).map_or(0, |n| u32::BITS - n.leading_zeros());
+            if used + bits > u128::BITS {
+                return Err(format!(
+                    "visit limits need more than {} bits to count, {} is one cave too many",
+                    u128::BITS, graph.name(cave)
+                ))
+            }
+            shift.push((bits != 0).then_some(used));
+            mask.push(((1u128 << bits) - 1) << used);
+            used += bits;
+        }
+        Ok(VisitKey { shift, mask })
+    }
+
+    fn get(&self, key: u128, cave: usize) -> u32 {
+        self.shift[cave].map_or(0, |s| ((key & self.mask[cave]) >> s) as u32)
+    }
+
+    // one more or one fewer visit, which stays within the cave's bits as long as
+    // its rule is respected; untracked caves leave the key unchanged
+    fn step(&self, cave: usize) -> u128 {
+        self.shift[cave].map_or(0, |s| 1 << s)
+    }
+}
+
+struct Counter<'a> {
+    graph: &'a CaveGraph,
+    rules: Rules,
+    key: VisitKey,
+    // visits to each tracked cave
+    visits: u128,
+    extra: u32,
+    memo: HashMap<(usize, u128), u64>
+}
+
+impl<'a> Counter<'a> {
+    // memoised on the current cave and how often each limited cave has been visited,
+    // which also determines how many extra visits have been used
+    fn count_from(&mut self, cave: usize) -> u64 {
+        if cave == self.graph.end {
+            return 1
+        }
+        if let Some(&n) = self.memo.get(&(cave, self.visits)) {
+            return n
+        }
+        let mut sum = 0;
+        for idx in 0..self.graph.adj[cave].len() {
+            let to = self.graph.adj[cave][idx];
+            let visits = self.key.get(self.visits, to);
+            if !self.rules.can_enter(to, visits, self.extra) {
+                continue
+            }
+            let is_extra = self.rules.becomes_extra(to, visits);
+            self.extra += is_extra as u32;
+            self.visits += self.key.step(to);
+            sum += self.count_from(to);
+            self.visits -= self.key.step(to);
+            self.extra -= is_extra as u32;
+        }
+        self.memo.insert((cave, self.visits), sum);
+        sum
+    }
+}
+
+pub(crate) fn count_paths(graph: &CaveGraph, policy: &VisitPolicy) -> Result<u64, String> {
+    let rules = policy.resolve(graph);
+    let mut counter = Counter {
+        graph,
+        key: VisitKey::new(graph, &rules)?,
+        rules,
+        visits: 0,
+        extra: 0,
+        memo: HashMap::new()
+    };
+    Ok(counter.count_from(graph.start))
+}
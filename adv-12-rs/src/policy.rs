@@ -0,0 +1,110 @@
+use std::collections::{HashMap, HashSet};
+use crate::CaveGraph;
+
+// how often each cave may be visited on a single path
+#[derive(Clone, Debug)]
+pub(crate) struct VisitPolicy {
+    // visits allowed to any small cave
+    pub(crate) small_limit: u32,
+    // number of small caves which may instead be visited up to extra_limit times
+    pub(crate) extra_caves: u32,
+    pub(crate) extra_limit: u32,
+    // fixed limits for particular caves, big or small, which ignore the above
+    pub(crate) limits: HashMap<String, u32>,
+    pub(crate) forbidden: HashSet<String>
+}
+
+impl VisitPolicy {
+    pub(crate) fn part1() -> Self {
+        VisitPolicy {
+            small_limit: 1,
+            extra_caves: 0,
+            extra_limit: 1,
+            limits: HashMap::new(),
+            forbidden: HashSet::new()
+        }
+    }
+
+    pub(crate) fn part2() -> Self {
+        VisitPolicy {
+            extra_caves: 1,
+            extra_limit: 2,
+            ..VisitPolicy::part1()
+        }
+    }
+
+    pub(crate) fn resolve(&self, graph: &CaveGraph) -> Rules {
+        let caves = graph.names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| {
+                if id == graph.start || self.forbidden.contains(name) {
+                    CaveRule::Forbidden
+                } else if let Some(&n) = self.limits.get(name) {
+                    CaveRule::Fixed(n)
+                } else if graph.big[id] {
+                    CaveRule::Unlimited
+                } else {
+                    CaveRule::Small
+                }
+            })
+            .collect();
+        Rules {
+            caves,
+            small_limit: self.small_limit,
+            extra_caves: self.extra_caves,
+            extra_limit: self.extra_limit
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum CaveRule {
+    Forbidden,
+    Unlimited,
+    Fixed(u32),
+    Small
+}
+
+// a policy applied to the caves of a particular graph
+pub(crate) struct Rules {
+    caves: Vec<CaveRule>,
+    small_limit: u32,
+    extra_caves: u32,
+    extra_limit: u32
+}
+
+impl Rules {
+    // most visits a path can make to a limited cave, None for caves whose visits don't matter
+    pub(crate) fn max_visits(&self, cave: usize) -> Option<u32> {
+        match self.caves[cave] {
+            CaveRule::Fixed(n) => Some(n),
+            CaveRule::Small if self.extra_caves > 0 => Some(self.small_limit.max(self.extra_limit)),
+            CaveRule::Small => Some(self.small_limit),
+            CaveRule::Forbidden | CaveRule::Unlimited => None
+        }
+    }
+
+    // extra is the number of small caves already visited more than small_limit times
+    pub(crate) fn can_enter(&self, cave: usize, visits: u32, extra: u32) -> bool {
+        match self.caves[cave] {
+            CaveRule::Forbidden => false,
+            CaveRule::Unlimited => true,
+            CaveRule::Fixed(n) => visits < n,
+            CaveRule::Small => {
+                if visits < self.small_limit {
+                    true
+                } else if visits >= self.extra_limit {
+                    false
+                } else {
+                    visits > self.small_limit || extra < self.extra_caves
+                }
+            }
+        }
+    }
+
+    // whether entering the cave turns it into one of the extra caves
+    pub(crate) fn becomes_extra(&self, cave: usize, visits: u32) -> bool {
+        self.caves[cave] == CaveRule::Small && visits == self.small_limit
+    }
+}
@@ -1,6 +1,8 @@
 #![feature(bool_to_option)]
 #![feature(destructuring_assignment)]
 
+mod parse;
+
 use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 use std::fs::File;
@@ -44,78 +46,95 @@ impl SnailNum {
         }
     }
 
-    fn assume_lit(&self) -> u32 {
+    fn as_lit(&self) -> Option<u32> {
         match self {
-            SnailNum::Lit(n) => *n,
-            _ => unreachable!()
+            SnailNum::Lit(n) => Some(*n),
+            _ => None
         }
     }
 
-    fn attempt_explode(&mut self, l: Option<&mut u32>, r: Option<&mut u32>, depth: usize) -> bool {
+    // depth is the number of pairs self is nested inside, returned with the exploded pair
+    fn attempt_explode(&mut self, l: Option<&mut u32>, r: Option<&mut u32>, depth: usize) -> Option<(u32, u32, usize)> {
         match self {
-            SnailNum::Lit(n) => false,
+            SnailNum::Lit(n) => None,
             SnailNum::Pair(a, b) => {
-                if depth == 0 {
+                if let (true, Some(a), Some(b)) = (depth >= 4, a.as_lit(), b.as_lit()) {
                     if let Some(l) = l {
-                        *l += a.assume_lit()
+                        *l += a
                     }
                     if let Some(r) = r {
-                        *r += b.assume_lit()
+                        *r += b
                     }
                     *self = SnailNum::Lit(0);
-                    true
+                    Some((a, b, depth))
                 } else {
-                    a.attempt_explode(l, Some(b.leftmost()), depth - 1)
-                        || b.attempt_explode(Some(a.rightmost()), r, depth - 1)
+                    let depth = depth + 1;
+                    a.attempt_explode(l, Some(b.leftmost()), depth)
+                        .or_else(|| b.attempt_explode(Some(a.rightmost()), r, depth))
                 }
             }
         }
     }
 
-    fn attempt_split(&mut self) -> bool {
+    fn attempt_split(&mut self, depth: usize) -> Option<(u32, usize)> {
         match self {
             SnailNum::Lit(n) if *n >= 10 => {
-                let a = *n / 2;
-                let b = *n - a;
+                let v = *n;
+                let a = v / 2;
+                let b = v - a;
                 let a = Box::new(SnailNum::Lit(a));
                 let b = Box::new(SnailNum::Lit(b));
                 *self = SnailNum::Pair(a, b);
-                true
+                Some((v, depth))
             }
             SnailNum::Pair(a, b) => {
-                a.attempt_split() || b.attempt_split()
+                a.attempt_split(depth + 1).or_else(|| b.attempt_split(depth + 1))
             }
-            _ => false
+            _ => None
         }
     }
 
     fn fully_explode(&mut self) -> bool {
-        if !self.attempt_explode(None, None, 4) {
+        if self.attempt_explode(None, None, 0).is_none() {
             return false
         }
-        while self.attempt_explode(None, None, 4) {}
+        while self.attempt_explode(None, None, 0).is_some() {}
         true
     }
 
     fn fully_split(&mut self) -> bool {
-        if !self.attempt_split() {
+        if self.attempt_split(0).is_none() {
             return false
         }
-        while self.attempt_split() {}
+        while self.attempt_split(0).is_some() {}
         true
     }
 
-    fn normalize(&mut self) {
-        let mut dirty = true;
-        while dirty {
-            dirty = false;
-            while self.attempt_explode(None, None, 4) {
-                dirty = true;
+    fn reduce_with(&mut self, mut f: impl FnMut(ReduceAction, &SnailNum)) {
+        loop {
+            if let Some((a, b, depth)) = self.attempt_explode(None, None, 0) {
+                f(ReduceAction::Explode(a, b, depth), self)
+            } else if let Some((n, depth)) = self.attempt_split(0) {
+                f(ReduceAction::Split(n, depth), self)
+            } else {
+                break
             }
-            dirty = self.attempt_split()
         }
     }
 
+    fn normalize(&mut self) {
+        self.reduce_with(|_, _| {})
+    }
+
+    fn normalize_traced(&mut self) -> Vec<ReduceStep> {
+        let mut trace = Vec::new();
+        self.reduce_with(|action, n| trace.push(ReduceStep {
+            action,
+            result: n.to_string()
+        }));
+        trace
+    }
+
     fn mag(&self) -> u32 {
         match self {
             SnailNum::Lit(n) => *n,
@@ -136,67 +155,85 @@ impl Add for SnailNum {
     }
 }
 
-impl Display for SnailNum {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            SnailNum::Lit(n) => f.write_fmt(format_args!("{}", n)),
-            SnailNum::Pair(a, b) => f.write_fmt(format_args!("[{},{}]", a, b))
-        }
+impl SnailNum {
+    fn add_traced(self, rhs: Self) -> (Self, Vec<ReduceStep>) {
+        let mut r = SnailNum::Pair(Box::new(self), Box::new(rhs));
+        let mut trace = vec![ReduceStep {
+            action: ReduceAction::Addition,
+            result: r.to_string()
+        }];
+        trace.extend(r.normalize_traced());
+        (r, trace)
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
-enum SnailToken {
-    Open,
-    Num(u32)
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum ReduceAction {
+    Addition,
+    // exploded pair and the number of pairs it was nested inside
+    Explode(u32, u32, usize),
+    // split literal and the number of pairs it was nested inside
+    Split(u32, usize)
 }
 
-fn tokenise<'a>(mut s: &'a str) -> impl 'a + Iterator<Item=SnailToken> {
-    from_fn(move || {
-        loop {
-            if s.len() == 0 {
-                return None
-            } else if s.starts_with('[') {
-                s = &s[1..];
-                return Some(SnailToken::Open)
-            } else if s.starts_with([',', ']', '\n']) {
-                s = &s[1..];
-            } else {
-                let pos = s.char_indices()
-                    .take_while(|&(n, c)| c.is_ascii_digit())
-                    .last().unwrap()
-                    .0;
-                let p = &s[..=pos];
-                s = &s[pos + 1..];
-                return Some(SnailToken::Num(p.parse().unwrap()));
-            }
+#[derive(Clone)]
+struct ReduceStep {
+    action: ReduceAction,
+    result: String
+}
+
+impl Display for ReduceStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.action {
+            ReduceAction::Addition => f.write_fmt(format_args!("after addition: {}", self.result)),
+            ReduceAction::Explode(a, b, depth) => f.write_fmt(format_args!(
+                "after explode:  {}    ([{},{}] at depth {})", self.result, a, b, depth
+            )),
+            ReduceAction::Split(n, depth) => f.write_fmt(format_args!(
+                "after split:    {}    ({} at depth {})", self.result, n, depth
+            ))
         }
-    })
+    }
 }
 
-fn parse_single(it: &mut impl Iterator<Item=SnailToken>) -> SnailNum {
-    match it.next().unwrap() {
-        SnailToken::Open => {
-            let a = parse_single(it);
-            let b = parse_single(it);
-            SnailNum::Pair(Box::new(a), Box::new(b))
+impl Display for SnailNum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnailNum::Lit(n) => f.write_fmt(format_args!("{}", n)),
+            SnailNum::Pair(a, b) => f.write_fmt(format_args!("[{},{}]", a, b))
         }
-        SnailToken::Num(n) => SnailNum::Lit(n)
     }
 }
 
 fn read_data() -> impl Iterator<Item=SnailNum> {
-    read_lines().map(|line| parse_single(&mut tokenise(line.as_str())))
+    read_lines().enumerate().map(|(idx, line)| {
+        line.parse().unwrap_or_else(|e| panic!("line {}: {}", idx + 1, e))
+    })
 }
 
 fn main() {
     let mut data = read_data().collect::<Vec<_>>();
 
+    let explain = std::env::args().any(|a| a == "--explain");
+
     let mut acc = data[0].clone();
     for i in 1..data.len() {
-        acc = acc + data[i].clone()
+        if explain {
+            let (r, trace) = acc.add_traced(data[i].clone());
+            for step in trace {
+                println!("{}", step)
+            }
+            println!();
+            acc = r
+        } else {
+            acc = acc + data[i].clone()
+        }
     }
     println!("1> {}", acc.mag());
+    #[cfg(feature = "serde")]
+    if std::env::args().any(|a| a == "--json") {
+        println!("{}", serde_json::to_string(&acc).unwrap());
+    }
 
     let mut best = 0;
     for i in 0..data.len() {
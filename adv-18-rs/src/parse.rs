@@ -0,0 +1,163 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::SnailNum;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    NumberTooLarge,
+    TrailingInput
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) struct ParseError {
+    // byte offset into the input
+    pub(crate) pos: usize,
+    pub(crate) kind: ParseErrorKind
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ParseErrorKind::UnexpectedChar(c) => f.write_fmt(format_args!("unexpected {:?} at {}", c, self.pos)),
+            ParseErrorKind::UnexpectedEnd => f.write_fmt(format_args!("unexpected end of input at {}", self.pos)),
+            ParseErrorKind::NumberTooLarge => f.write_fmt(format_args!("number too large at {}", self.pos)),
+            ParseErrorKind::TrailingInput => f.write_fmt(format_args!("trailing input at {}", self.pos))
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Self {
+        Parser { s, pos: 0 }
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError { pos: self.pos, kind }
+    }
+
+    fn skip_white(&mut self) {
+        self.pos += self.s[self.pos..].len() - self.s[self.pos..].trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_white();
+        self.s[self.pos..].chars().next()
+    }
+
+    fn expect(&mut self, want: char) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(c) if c == want => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            Some(c) => Err(self.error(ParseErrorKind::UnexpectedChar(c))),
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd))
+        }
+    }
+
+    fn parse_num(&mut self) -> Result<SnailNum, ParseError> {
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let a = self.parse_num()?;
+                self.expect(',')?;
+                let b = self.parse_num()?;
+                self.expect(']')?;
+                Ok(SnailNum::Pair(Box::new(a), Box::new(b)))
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let len = self.s[self.pos..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(self.s.len() - self.pos);
+                let n = self.s[self.pos..][..len].parse()
+                    .map_err(|_| self.error(ParseErrorKind::NumberTooLarge))?;
+                self.pos += len;
+                Ok(SnailNum::Lit(n))
+            }
+            Some(c) => Err(self.error(ParseErrorKind::UnexpectedChar(c))),
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd))
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), ParseError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error(ParseErrorKind::TrailingInput))
+        }
+    }
+}
+
+impl FromStr for SnailNum {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser::new(s);
+        let n = p.parse_num()?;
+        p.finish()?;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::fmt::Formatter;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use crate::SnailNum;
+
+    impl Serialize for SnailNum {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                SnailNum::Lit(n) => serializer.serialize_u32(*n),
+                SnailNum::Pair(a, b) => {
+                    let mut t = serializer.serialize_tuple(2)?;
+                    t.serialize_element(a)?;
+                    t.serialize_element(b)?;
+                    t.end()
+                }
+            }
+        }
+    }
+
+    struct SnailVisitor;
+
+    impl<'de> Visitor<'de> for SnailVisitor {
+        type Value = SnailNum;
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            f.write_str("a non-negative integer or a two element array")
+        }
+
+        fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+            u32::try_from(v)
+                .map(SnailNum::Lit)
+                .map_err(|_| E::custom(format_args!("number too large: {}", v)))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let a = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+            let b = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(1, &self))?;
+            if seq.next_element::<SnailNum>()?.is_some() {
+                return Err(A::Error::invalid_length(3, &self))
+            }
+            Ok(SnailNum::Pair(Box::new(a), Box::new(b)))
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SnailNum {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(SnailVisitor)
+        }
+    }
+}
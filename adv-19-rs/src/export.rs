@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use crate::{PlacedChunk, Vec3i};
+
+pub(crate) struct WorldMap<'a> {
+    pub(crate) scanners: &'a [PlacedChunk],
+    // beacons in scanner 0 coordinates, sorted
+    pub(crate) beacons: Vec<Vec3i>,
+    // (placed by, placed) scanner id pairs in the order they were aligned
+    pub(crate) edges: &'a [(i32, i32)],
+}
+
+fn scanner_pos(pc: &PlacedChunk) -> Vec3i {
+    Vec3i::new(0, 0, 0).apply_rt(&pc.1)
+}
+
+fn fmt_vec(v: &Vec3i) -> String {
+    let [x, y, z] = v.0.backing;
+    format!("[{},{},{}]", x, y, z)
+}
+
+impl<'a> WorldMap<'a> {
+    pub(crate) fn write_json(&self, path: &str) -> Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"scanners\": [")?;
+        for (idx, pc) in self.scanners.iter().enumerate() {
+            let r = pc.1.rot.0.backing;
+            writeln!(
+                w, "    {{\"id\": {}, \"position\": {}, \"rotation\": [[{},{},{}],[{},{},{}],[{},{},{}]]}}{}",
+                pc.2, fmt_vec(&scanner_pos(pc)),
+                r[0], r[1], r[2], r[3], r[4], r[5], r[6], r[7], r[8],
+                if idx + 1 == self.scanners.len() { "" } else { "," }
+            )?;
+        }
+        writeln!(w, "  ],")?;
+        writeln!(w, "  \"edges\": [")?;
+        for (idx, (a, b)) in self.edges.iter().enumerate() {
+            writeln!(w, "    [{},{}]{}", a, b, if idx + 1 == self.edges.len() { "" } else { "," })?;
+        }
+        writeln!(w, "  ],")?;
+        writeln!(w, "  \"beacons\": [")?;
+        for (idx, v) in self.beacons.iter().enumerate() {
+            writeln!(w, "    {}{}", fmt_vec(v), if idx + 1 == self.beacons.len() { "" } else { "," })?;
+        }
+        writeln!(w, "  ]")?;
+        writeln!(w, "}}")?;
+        w.flush()
+    }
+
+    // beacons are white, scanners are red
+    pub(crate) fn write_ply(&self, path: &str) -> Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        writeln!(w, "ply")?;
+        writeln!(w, "format ascii 1.0")?;
+        writeln!(w, "element vertex {}", self.beacons.len() + self.scanners.len())?;
+        writeln!(w, "property int x")?;
+        writeln!(w, "property int y")?;
+        writeln!(w, "property int z")?;
+        writeln!(w, "property uchar red")?;
+        writeln!(w, "property uchar green")?;
+        writeln!(w, "property uchar blue")?;
+        writeln!(w, "end_header")?;
+        for v in self.beacons.iter() {
+            let [x, y, z] = v.0.backing;
+            writeln!(w, "{} {} {} 255 255 255", x, y, z)?;
+        }
+        for pc in self.scanners.iter() {
+            let [x, y, z] = scanner_pos(pc).0.backing;
+            writeln!(w, "{} {} {} 255 0 0", x, y, z)?;
+        }
+        w.flush()
+    }
+
+    // scanners are vertices in group "scanners", alignment edges are lines between them
+    pub(crate) fn write_obj(&self, path: &str) -> Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        writeln!(w, "o beacons")?;
+        for v in self.beacons.iter() {
+            let [x, y, z] = v.0.backing;
+            writeln!(w, "v {} {} {}", x, y, z)?;
+        }
+        for i in 1..=self.beacons.len() {
+            writeln!(w, "p {}", i)?;
+        }
+        writeln!(w, "o scanners")?;
+        for pc in self.scanners.iter() {
+            let [x, y, z] = scanner_pos(pc).0.backing;
+            writeln!(w, "v {} {} {}", x, y, z)?;
+        }
+        let vert_of = |id: i32| self.scanners
+            .iter()
+            .position(|pc| pc.2 == id)
+            .map(|idx| self.beacons.len() + idx + 1);
+        for (a, b) in self.edges.iter() {
+            if let (Some(a), Some(b)) = (vert_of(*a), vert_of(*b)) {
+                writeln!(w, "l {} {}", a, b)?;
+            }
+        }
+        w.flush()
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use crate::{ALL_ROTATIONS, contains_near, MatchParams, refine_trans, RotAndTrans, Vec3i};
+
+fn dist(a: &Vec3i, b: &Vec3i) -> f64 {
+    let d = *a - *b;
+    d.0.backing.iter().map(|&v| (v as f64) * (v as f64)).sum::<f64>().sqrt()
+}
+
+// size of the largest matching between two sorted lists, pairing values at most eps apart
+fn count_shared(a: &[f64], b: &[f64], eps: f64) -> usize {
+    let (mut i, mut j, mut n) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        if (a[i] - b[j]).abs() <= eps {
+            n += 1;
+            i += 1;
+            j += 1;
+        } else if a[i] < b[j] {
+            i += 1
+        } else {
+            j += 1
+        }
+    }
+    n
+}
+
+// rotation and translation invariant summary of a scanner's beacons
+pub(crate) struct Fingerprint {
+    // sorted distances between every pair of beacons
+    dists: Vec<f64>,
+    // each beacon along with its sorted distances to every other beacon
+    points: Vec<(Vec3i, Vec<f64>)>,
+}
+
+impl Fingerprint {
+    pub(crate) fn new(beacons: &HashSet<Vec3i>) -> Self {
+        let beacons = beacons.iter().copied().collect::<Vec<_>>();
+        let mut dists = Vec::new();
+        let mut points = beacons
+            .iter()
+            .map(|&v| (v, Vec::new()))
+            .collect::<Vec<_>>();
+        for i in 0..beacons.len() {
+            for j in (i + 1)..beacons.len() {
+                let d = dist(&beacons[i], &beacons[j]);
+                dists.push(d);
+                points[i].1.push(d);
+                points[j].1.push(d);
+            }
+        }
+        dists.sort_by(f64::total_cmp);
+        for (_, ds) in points.iter_mut() {
+            ds.sort_by(f64::total_cmp);
+        }
+        Fingerprint {
+            dists,
+            points
+        }
+    }
+
+    // number of pairwise distances the two scanners have in common
+    pub(crate) fn shared(&self, oth: &Self, eps: f64) -> usize {
+        count_shared(self.dists.as_slice(), oth.dists.as_slice(), eps)
+    }
+
+    // pairs of beacons which share enough distances to plausibly be the same beacon
+    pub(crate) fn correspondences(&self, oth: &Self, min_shared: usize, eps: f64) -> Vec<(Vec3i, Vec3i)> {
+        let mut ret = Vec::new();
+        for (a, a_dists) in self.points.iter() {
+            for (b, b_dists) in oth.points.iter() {
+                if count_shared(a_dists.as_slice(), b_dists.as_slice(), eps) >= min_shared {
+                    ret.push((*a, *b))
+                }
+            }
+        }
+        ret
+    }
+}
+
+pub(crate) fn try_find_rt_fp(
+    socket: &HashSet<Vec3i>, socket_fp: &Fingerprint,
+    plug: &HashSet<Vec3i>, plug_fp: &Fingerprint,
+    params: &MatchParams
+) -> Option<RotAndTrans> {
+    let overlap = params.overlap;
+    let eps = params.fingerprint_radius();
+    if socket_fp.shared(plug_fp, eps) < overlap * overlap.saturating_sub(1) / 2 {
+        return None
+    }
+    let pairs = socket_fp.correspondences(plug_fp, overlap.saturating_sub(1), eps);
+    if pairs.len() < overlap {
+        return None
+    }
+    for rot in ALL_ROTATIONS.iter().copied() {
+        let trans = pairs
+            .iter()
+            .map(|(s, p)| *s - p.apply_rot(&rot))
+            .collect::<Vec<_>>();
+        let (trans, n) = trans
+            .iter()
+            .map(|t| (*t, trans.iter().filter(|u| t.near(u, params.near_radius())).count()))
+            .max_by_key(|&(_, n)| n)?;
+        if n < overlap {
+            continue
+        }
+        let rot_plug = plug.iter().map(|v| v.apply_rot(&rot)).collect::<Vec<_>>();
+        let trans = refine_trans(socket, rot_plug.as_slice(), trans, params.near_radius());
+        let hits = rot_plug
+            .iter()
+            .filter(|v| contains_near(socket, &(**v + trans), params.near_radius()))
+            .count();
+        if hits >= overlap {
+            return Some(RotAndTrans {
+                rot,
+                trans
+            })
+        }
+    }
+    None
+}
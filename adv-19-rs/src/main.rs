@@ -6,12 +6,17 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod fingerprint;
+mod export;
+
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::iter::from_fn;
 use std::mem::swap;
 use std::ops::{Add, Mul, Sub};
+use crate::export::WorldMap;
+use crate::fingerprint::{Fingerprint, try_find_rt_fp};
 
 const FILENAME: &'static str = "i1.txt";
 
@@ -122,6 +127,10 @@ impl Vec3i {
         })
     }
 
+    fn near(&self, oth: &Self, tol: i32) -> bool {
+        (0..3).all(|i| (self.0.backing[i] - oth.0.backing[i]).abs() <= tol)
+    }
+
     fn man_dist(&self, oth: &Self) -> i32 {
         let mut acc = 0;
         for i in 0..3 {
@@ -131,6 +140,84 @@ impl Vec3i {
     }
 }
 
+fn contains_near(set: &HashSet<Vec3i>, v: &Vec3i, tol: i32) -> bool {
+    if tol == 0 {
+        return set.contains(v)
+    }
+    for dx in -tol..=tol {
+        for dy in -tol..=tol {
+            for dz in -tol..=tol {
+                if set.contains(&(*v + Vec3i::new(dx, dy, dz))) {
+                    return true
+                }
+            }
+        }
+    }
+    false
+}
+
+// the member of set closest to v, if any is within tol in every coordinate
+fn find_near(set: &HashSet<Vec3i>, v: &Vec3i, tol: i32) -> Option<Vec3i> {
+    let mut best: Option<Vec3i> = None;
+    for dx in -tol..=tol {
+        for dy in -tol..=tol {
+            for dz in -tol..=tol {
+                let u = *v + Vec3i::new(dx, dy, dz);
+                if set.contains(&u) && best.map_or(true, |b| u.man_dist(v) < b.man_dist(v)) {
+                    best = Some(u)
+                }
+            }
+        }
+    }
+    best
+}
+
+// consensus translation of the rotated plug onto the socket, the per-coordinate median
+// over every beacon pair within tol of each other under the rough translation given
+fn refine_trans(socket: &HashSet<Vec3i>, rot_plug: &[Vec3i], trans: Vec3i, tol: i32) -> Vec3i {
+    if tol == 0 {
+        return trans
+    }
+    let offsets = rot_plug
+        .iter()
+        .filter_map(|v| Some(find_near(socket, &(*v + trans), tol)? - *v))
+        .collect::<Vec<_>>();
+    if offsets.is_empty() {
+        return trans
+    }
+    let mut ret = [0; 3];
+    for i in 0..3 {
+        let mut c = offsets.iter().map(|o| o.0.backing[i]).collect::<Vec<_>>();
+        c.sort();
+        ret[i] = c[c.len() / 2]
+    }
+    Vec3i::new(ret[0], ret[1], ret[2])
+}
+
+#[derive(Copy, Clone, Debug)]
+struct MatchParams {
+    // beacons two scanners must have in common to be aligned
+    overlap: usize,
+    // largest per-coordinate difference between two readings of the same beacon
+    tolerance: i32,
+}
+
+impl MatchParams {
+    // how far apart two readings of the same beacon may be in any coordinate once placed:
+    // each reading is off by up to tolerance, and the translation between their scanners,
+    // being the difference of two more readings, by up to twice that
+    fn near_radius(&self) -> i32 {
+        4 * self.tolerance
+    }
+
+    // the same bound on the length of the vector between two beacons, compared across
+    // scanners: each end is off by up to tolerance in either scanner, so the vector is
+    // off by up to the near radius in all three coordinates
+    fn fingerprint_radius(&self) -> f64 {
+        3f64.sqrt() * self.near_radius() as f64 + 1e-9
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 struct Rot(Matrix<3, 3>);
 
@@ -232,8 +319,19 @@ impl FreeChunk {
         self.with_rt(combine_rt(rt, &pc.1))
     }
 
-    fn attempt_place_by(self, base: &PlacedChunk) -> Result<PlacedChunk, FreeChunk> {
-        match try_find_rt(&base.0, &self.0) {
+    fn attempt_place_by(
+        self, base: &PlacedChunk,
+        fps: Option<&[Fingerprint]>, params: &MatchParams
+    ) -> Result<PlacedChunk, FreeChunk> {
+        let rt = match fps {
+            Some(fps) => try_find_rt_fp(
+                &base.0, &fps[base.2 as usize],
+                &self.0, &fps[self.1 as usize],
+                params
+            ),
+            None => try_find_rt(&base.0, &self.0, params)
+        };
+        match rt {
             Some(rt) => {
                 Ok(self.with_rt_from(base, &rt))
             },
@@ -242,15 +340,20 @@ impl FreeChunk {
     }
 }
 
-fn combine_placed_chunks<'a>(it: impl Iterator<Item=&'a PlacedChunk>) -> HashSet<Vec3i> {
-    it
-        .flat_map(|p| {
-            println!("OFFSET: {:?}", Vec3i::new(0, 0, 0).apply_rt(&p.1));
-            p.0
-                .iter()
-                .map(move |v| v.apply_rt(&p.1))
-        })
-        .collect()
+fn combine_placed_chunks<'a>(it: impl Iterator<Item=&'a PlacedChunk>, tol: i32) -> HashSet<Vec3i> {
+    let mut ret = HashSet::new();
+    for v in it.flat_map(|p| {
+        println!("OFFSET: {:?}", Vec3i::new(0, 0, 0).apply_rt(&p.1));
+        p.0
+            .iter()
+            .map(move |v| v.apply_rt(&p.1))
+    }) {
+        // readings from different scanners, placed by noisy poses, may drift further apart
+        if !contains_near(&ret, &v, tol) {
+            ret.insert(v);
+        }
+    }
+    ret
 }
 
 const EMPTY_ROTATION: Rot = Rot::new([1, 0, 0, 0, 1, 0, 0, 0, 1]);
@@ -335,28 +438,30 @@ fn combine_rt(first: &RotAndTrans, second: &RotAndTrans) -> RotAndTrans {
     }
 }
 
-fn try_find_trans(socket: &HashSet<Vec3i>, plug: &[Vec3i]) -> Option<Vec3i> {
+fn try_find_trans(socket: &HashSet<Vec3i>, plug: &[Vec3i], params: &MatchParams) -> Option<Vec3i> {
     for sock_vec in socket.iter() {
         for j in 0..plug.len() {
             // try to pair i and j
             let d = *sock_vec - plug[j];
-            let mut new_ls = socket.clone();
-            new_ls.extend(plug.iter().map(|v| *v + d));
-            if (new_ls.len() + 12) <= (socket.len() + plug.len()) {
-                return Some(d);
+            let hits = plug
+                .iter()
+                .filter(|v| contains_near(socket, &(**v + d), params.near_radius()))
+                .count();
+            if hits >= params.overlap {
+                return Some(refine_trans(socket, plug, d, params.near_radius()));
             }
         }
     }
     return None;
 }
 
-fn try_find_rt(socket: &HashSet<Vec3i>, plug: &HashSet<Vec3i>) -> Option<RotAndTrans> {
+fn try_find_rt(socket: &HashSet<Vec3i>, plug: &HashSet<Vec3i>, params: &MatchParams) -> Option<RotAndTrans> {
     for rot in ALL_ROTATIONS.iter().copied() {
         let rot_plug = plug
             .iter().copied()
             .map(|v| v.apply_rot(&rot))
             .collect::<Vec<_>>();
-        if let Some(trans) = try_find_trans(socket, rot_plug.as_slice()) {
+        if let Some(trans) = try_find_trans(socket, rot_plug.as_slice(), params) {
             return Some(RotAndTrans {
                 rot,
                 trans,
@@ -383,10 +488,19 @@ fn try_remove<A, B>(
     }
 }
 
-fn p1(data: &[FreeChunk]) {
+fn p1(data: &[FreeChunk], use_fp: bool, params: &MatchParams) {
+    let fps = use_fp.then(|| data
+        .iter()
+        .map(|c| Fingerprint::new(&c.0))
+        .collect::<Vec<_>>()
+    );
     let mut complete_todo = vec![data[0].clone().place_origin()];
+    // beacons placed so far, which new poses are refined against so noise doesn't build up
+    // along chains of scanners
+    let mut world = combine_placed_chunks(complete_todo.iter(), params.near_radius());
     let mut data = Vec::from(&data[1..]);
     let mut complete_done = vec![];
+    let mut edges = vec![];
 
     loop {
         println!("LEFT: {}", data.len());
@@ -394,17 +508,37 @@ fn p1(data: &[FreeChunk]) {
             break
         } else {
             match complete_todo.pop() {
-                None => panic!("unfulfilled"),
+                None => {
+                    let mut unplaced = data.iter().map(|c| c.1).collect::<Vec<_>>();
+                    unplaced.sort();
+                    println!("could not place scanners: {:?}", unplaced);
+                    break
+                }
                 Some(pc) => {
                     let mut i = 0;
                     while i < data.len() {
                         match try_remove(
                             &mut data, i,
                             FreeChunk::blank(),
-                            |v| v.attempt_place_by(&pc)
+                            |v| v.attempt_place_by(&pc, fps.as_deref(), params)
                         ) {
-                            Some(new_p) => {
+                            Some(mut new_p) => {
+                                if params.tolerance != 0 {
+                                    let rot_beacons = new_p.0
+                                        .iter()
+                                        .map(|v| v.apply_rot(&new_p.1.rot))
+                                        .collect::<Vec<_>>();
+                                    new_p.1.trans = refine_trans(
+                                        &world, rot_beacons.as_slice(), new_p.1.trans, params.near_radius()
+                                    );
+                                    for v in rot_beacons.iter().map(|v| *v + new_p.1.trans) {
+                                        if !contains_near(&world, &v, params.near_radius()) {
+                                            world.insert(v);
+                                        }
+                                    }
+                                }
                                 println!("::: {} -> {} == {:?}", pc.2, new_p.2, new_p.1);
+                                edges.push((pc.2, new_p.2));
                                 complete_todo.push(new_p)
                             },
                             None => {
@@ -421,7 +555,25 @@ fn p1(data: &[FreeChunk]) {
     complete_done.extend(complete_todo.into_iter());
     let scanners = complete_done;
 
-    println!("1> {}", combine_placed_chunks(scanners.iter()).len());
+    let beacons = combine_placed_chunks(scanners.iter(), params.near_radius());
+    println!("1> {}", beacons.len());
+
+    let mut beacons = beacons.into_iter().collect::<Vec<_>>();
+    beacons.sort_by_key(|v| v.0.backing);
+    let map = WorldMap {
+        scanners: scanners.as_slice(),
+        beacons,
+        edges: edges.as_slice()
+    };
+    if let Some(path) = arg_value("--json") {
+        map.write_json(path.as_str()).unwrap();
+    }
+    if let Some(path) = arg_value("--ply") {
+        map.write_ply(path.as_str()).unwrap();
+    }
+    if let Some(path) = arg_value("--obj") {
+        map.write_obj(path.as_str()).unwrap();
+    }
 
     let mut max = 0;
     for i in 0..scanners.len() {
@@ -438,8 +590,19 @@ fn p1(data: &[FreeChunk]) {
     println!("2> {}", max)
 }
 
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
+}
+
 fn main() {
     let data = read_data();
 
-    p1(data.as_slice())
+    let params = MatchParams {
+        overlap: arg_value("--overlap").map_or(12, |v| v.parse().unwrap()),
+        tolerance: arg_value("--tolerance").map_or(0, |v| v.parse().unwrap())
+    };
+
+    p1(data.as_slice(), !std::env::args().any(|a| a == "--brute"), &params)
 }
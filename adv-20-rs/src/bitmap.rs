@@ -0,0 +1,172 @@
+use std::thread;
+
+// dense image covering rows [y0, y0 + height) and columns [x0, x0 + width),
+// with every pixel outside of that set to the background
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct BitMap {
+    y0: i32,
+    x0: i32,
+    height: usize,
+    width: usize,
+    // u64 words per row
+    stride: usize,
+    bits: Vec<u64>,
+    is_invert: bool
+}
+
+impl BitMap {
+    pub(crate) fn new(y0: i32, x0: i32, height: usize, width: usize, is_invert: bool) -> Self {
+        let stride = (width + 63) / 64;
+        BitMap {
+            y0,
+            x0,
+            height,
+            width,
+            stride,
+            bits: vec![0; stride * height],
+            is_invert
+        }
+    }
+
+    pub(crate) fn from_light_list(it: impl Iterator<Item=(i32, i32)>) -> Self {
+        let ls = it.collect::<Vec<_>>();
+        let y0 = ls.iter().map(|p| p.0).min().unwrap_or(0);
+        let x0 = ls.iter().map(|p| p.1).min().unwrap_or(0);
+        let y1 = ls.iter().map(|p| p.0 + 1).max().unwrap_or(0);
+        let x1 = ls.iter().map(|p| p.1 + 1).max().unwrap_or(0);
+        let mut ret = BitMap::new(y0, x0, (y1 - y0) as usize, (x1 - x0) as usize, false);
+        for (y, x) in ls {
+            ret.set_pixel(y, x, true)
+        }
+        ret
+    }
+
+    pub(crate) fn bounds(&self) -> (i32, i32, usize, usize) {
+        (self.y0, self.x0, self.height, self.width)
+    }
+
+    pub(crate) fn is_invert(&self) -> bool {
+        self.is_invert
+    }
+
+    fn get_local(&self, row: isize, col: isize) -> bool {
+        if row < 0 || col < 0 || row as usize >= self.height || col as usize >= self.width {
+            self.is_invert
+        } else {
+            let (row, col) = (row as usize, col as usize);
+            (self.bits[row * self.stride + col / 64] >> (col % 64)) & 1 != 0
+        }
+    }
+
+    pub(crate) fn read_pixel(&self, y: i32, x: i32) -> bool {
+        self.get_local((y - self.y0) as isize, (x - self.x0) as isize)
+    }
+
+    // panics if the pixel lies outside of the stored area
+    pub(crate) fn set_pixel(&mut self, y: i32, x: i32, v: bool) {
+        let row = (y - self.y0) as usize;
+        let col = (x - self.x0) as usize;
+        assert!(row < self.height && col < self.width, "pixel outside of image");
+        let word = &mut self.bits[row * self.stride + col / 64];
+        if v {
+            *word |= 1 << (col % 64)
+        } else {
+            *word &= !(1 << (col % 64))
+        }
+    }
+
+    pub(crate) fn get_blank_id(&self) -> usize {
+        if self.is_invert {
+            511
+        } else {
+            0
+        }
+    }
+
+    // fills one row of the enhanced image, which is one pixel larger on every side
+    fn enhance_row(&self, f: &impl Fn(usize) -> bool, new_row: usize, out: &mut [u64]) {
+        let mid = new_row as isize - 1;
+        let push = |idx: usize, col: isize| {
+            ((idx << 1) & 0b110_110_110)
+                | (self.get_local(mid - 1, col) as usize) << 6
+                | (self.get_local(mid, col) as usize) << 3
+                | (self.get_local(mid + 1, col) as usize)
+        };
+        let mut idx = push(push(0, -2), -1);
+        for new_col in 0..(self.width + 2) {
+            idx = push(idx, new_col as isize);
+            if f(idx) {
+                out[new_col / 64] |= 1 << (new_col % 64)
+            }
+        }
+    }
+
+    pub(crate) fn enhance(&self, f: &(impl Fn(usize) -> bool + Sync), threads: usize) -> Self {
+        let mut ret = BitMap::new(
+            self.y0 - 1, self.x0 - 1,
+            self.height + 2, self.width + 2,
+            f(self.get_blank_id())
+        );
+        let stride = ret.stride;
+        let threads = threads.max(1);
+        let rows_per = (ret.height + threads - 1) / threads;
+        thread::scope(|s| {
+            for (chunk_idx, chunk) in ret.bits.chunks_mut(stride * rows_per).enumerate() {
+                s.spawn(move || {
+                    for (i, out) in chunk.chunks_mut(stride).enumerate() {
+                        self.enhance_row(f, chunk_idx * rows_per + i, out)
+                    }
+                });
+            }
+        });
+        ret
+    }
+
+    pub(crate) fn enhance_count(&mut self, f: &(impl Fn(usize) -> bool + Sync), n: usize, threads: usize) {
+        for _ in 0..n {
+            *self = self.enhance(f, threads)
+        }
+    }
+
+    // smallest image holding every pixel which differs from the background,
+    // moved to the origin, along with where its top left corner used to be
+    pub(crate) fn crop(&self) -> (Self, (i32, i32)) {
+        let mut rows = (self.height, 0);
+        let mut cols = (self.width, 0);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.get_local(row as isize, col as isize) != self.is_invert {
+                    rows = (rows.0.min(row), rows.1.max(row + 1));
+                    cols = (cols.0.min(col), cols.1.max(col + 1));
+                }
+            }
+        }
+        if rows.0 >= rows.1 {
+            return (BitMap::new(0, 0, 0, 0, self.is_invert), (self.y0, self.x0))
+        }
+        let mut ret = BitMap::new(0, 0, rows.1 - rows.0, cols.1 - cols.0, self.is_invert);
+        for row in 0..ret.height {
+            for col in 0..ret.width {
+                let v = self.get_local((row + rows.0) as isize, (col + cols.0) as isize);
+                if v {
+                    ret.set_pixel(row as i32, col as i32, true)
+                }
+            }
+        }
+        (ret, (self.y0 + rows.0 as i32, self.x0 + cols.0 as i32))
+    }
+
+    pub(crate) fn moved_to(mut self, y0: i32, x0: i32) -> Self {
+        self.y0 = y0;
+        self.x0 = x0;
+        self
+    }
+
+    pub(crate) fn get_count(&self) -> usize {
+        if self.is_invert {
+            panic!("inverted image has infinite light pixels")
+        } else {
+            self.bits.iter().map(|w| w.count_ones() as usize).sum()
+        }
+    }
+}
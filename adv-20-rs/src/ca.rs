@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use crate::bitmap::BitMap;
+
+// next state for each 3x3 neighbourhood, indexed as in Map::number_in_area
+// (top left is the most significant bit, the centre is bit 4)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct Rule([u64; 8]);
+
+impl Rule {
+    pub(crate) fn from_fn(f: impl Fn(usize) -> bool) -> Self {
+        let mut ret = [0; 8];
+        for idx in 0..512 {
+            if f(idx) {
+                ret[idx / 64] |= 1 << (idx % 64)
+            }
+        }
+        Rule(ret)
+    }
+
+    // 512 characters of '#' and '.', as in the puzzle input
+    pub(crate) fn from_table(s: &str) -> Option<Self> {
+        let table = s.chars().collect::<Vec<_>>();
+        if table.len() != 512 || table.iter().any(|&c| c != '#' && c != '.') {
+            return None
+        }
+        Some(Rule::from_fn(|idx| table[idx] == '#'))
+    }
+
+    // outer totalistic rule counting the eight neighbours, e.g. B3/S23 for Life
+    pub(crate) fn from_birth_survive(birth: &[u32], survive: &[u32]) -> Self {
+        Rule::from_fn(|idx| {
+            let n = (idx & !0b000_010_000).count_ones();
+            if idx & 0b000_010_000 != 0 {
+                survive.contains(&n)
+            } else {
+                birth.contains(&n)
+            }
+        })
+    }
+
+    // rules in "B3/S23" notation
+    pub(crate) fn from_life_notation(s: &str) -> Option<Self> {
+        let (b, s) = s.split_once('/')?;
+        let digits = |v: &str, prefix: char| -> Option<Vec<u32>> {
+            v.strip_prefix(prefix)?.chars().map(|c| c.to_digit(9)).collect()
+        };
+        Some(Rule::from_birth_survive(&digits(b, 'B')?, &digits(s, 'S')?))
+    }
+
+    pub(crate) fn get(&self, idx: usize) -> bool {
+        (self.0[idx / 64] >> (idx % 64)) & 1 != 0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Cycle {
+    // first generation of the repeating sequence
+    pub(crate) start: usize,
+    pub(crate) period: usize,
+    // how far the pattern moves every period
+    pub(crate) shift: (i32, i32)
+}
+
+impl Cycle {
+    pub(crate) fn is_stable(&self) -> bool {
+        self.period == 1 && self.shift == (0, 0)
+    }
+}
+
+pub(crate) struct Automaton {
+    rule: Rule,
+    img: BitMap,
+    generation: usize,
+    threads: usize
+}
+
+impl Automaton {
+    pub(crate) fn new(rule: Rule, img: BitMap, threads: usize) -> Self {
+        let (img, (y0, x0)) = img.crop();
+        Automaton {
+            rule,
+            img: img.moved_to(y0, x0),
+            generation: 0,
+            threads
+        }
+    }
+
+    pub(crate) fn image(&self) -> &BitMap {
+        &self.img
+    }
+
+    pub(crate) fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub(crate) fn step(&mut self) {
+        let rule = &self.rule;
+        let next = self.img.enhance(&|idx| rule.get(idx), self.threads);
+        // keeps the image from growing with every generation
+        let (img, (y0, x0)) = next.crop();
+        self.img = img.moved_to(y0, x0);
+        self.generation += 1;
+    }
+
+    // runs until a state repeats, up to some number of generations, with the
+    // pattern compared independent of where it is, so spaceships are found too
+    pub(crate) fn find_cycle(&mut self, max_steps: usize) -> Option<Cycle> {
+        let mut seen = HashMap::new();
+        for i in 0..=max_steps {
+            let (shape, pos) = self.img.crop();
+            if let Some(&(start, old_pos)) = seen.get(&shape) {
+                let (oy, ox): (i32, i32) = old_pos;
+                return Some(Cycle {
+                    start,
+                    period: self.generation - start,
+                    shift: (pos.0 - oy, pos.1 - ox)
+                })
+            }
+            seen.insert(shape, (self.generation, pos));
+            // the last generation checked is the one left in the image
+            if i < max_steps {
+                self.step();
+            }
+        }
+        None
+    }
+}
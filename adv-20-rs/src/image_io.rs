@@ -0,0 +1,137 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Result, Write, Error, ErrorKind};
+use crate::bitmap::BitMap;
+
+// lit pixels are stored as black (1) in every format, matching the '#' of the puzzle input
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn write_pbm(path: &str, img: &BitMap) -> Result<()> {
+    let (y0, x0, height, width) = img.bounds();
+    let mut w = BufWriter::new(File::create(path)?);
+    write!(w, "P4\n{} {}\n", width, height)?;
+    let mut row = vec![0u8; (width + 7) / 8];
+    for y in 0..height {
+        row.iter_mut().for_each(|b| *b = 0);
+        for x in 0..width {
+            if img.read_pixel(y0 + y as i32, x0 + x as i32) {
+                row[x / 8] |= 0x80 >> (x % 8)
+            }
+        }
+        w.write_all(row.as_slice())?;
+    }
+    w.flush()
+}
+
+// whitespace separated header fields, skipping comments
+fn pbm_header_field(data: &[u8], pos: &mut usize) -> Result<usize> {
+    loop {
+        match data.get(*pos) {
+            Some(b'#') => {
+                while data.get(*pos).map_or(false, |&c| c != b'\n') {
+                    *pos += 1
+                }
+            }
+            Some(c) if c.is_ascii_whitespace() => *pos += 1,
+            Some(_) => break,
+            None => return Err(invalid("truncated pbm header"))
+        }
+    }
+    let start = *pos;
+    while data.get(*pos).map_or(false, |c| c.is_ascii_digit()) {
+        *pos += 1
+    }
+    std::str::from_utf8(&data[start..*pos])
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid("bad pbm header"))
+}
+
+pub(crate) fn read_pbm(path: &str) -> Result<BitMap> {
+    let mut data = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut data)?;
+    let binary = match data.get(..2) {
+        Some(b"P1") => false,
+        Some(b"P4") => true,
+        _ => return Err(invalid("not a pbm file"))
+    };
+    let mut pos = 2;
+    let width = pbm_header_field(data.as_slice(), &mut pos)?;
+    let height = pbm_header_field(data.as_slice(), &mut pos)?;
+    let mut img = BitMap::new(0, 0, height, width, false);
+    if binary {
+        // single whitespace character after the header
+        pos += 1;
+        let stride = (width + 7) / 8;
+        let bits = data.get(pos..(pos + stride * height)).ok_or_else(|| invalid("truncated pbm data"))?;
+        for y in 0..height {
+            for x in 0..width {
+                if bits[y * stride + x / 8] & (0x80 >> (x % 8)) != 0 {
+                    img.set_pixel(y as i32, x as i32, true)
+                }
+            }
+        }
+    } else {
+        let mut bits = data[pos..]
+            .iter()
+            .filter(|c| matches!(c, b'0' | b'1'))
+            .map(|&c| c == b'1');
+        for y in 0..height {
+            for x in 0..width {
+                if bits.next().ok_or_else(|| invalid("truncated pbm data"))? {
+                    img.set_pixel(y as i32, x as i32, true)
+                }
+            }
+        }
+    }
+    Ok(img)
+}
+
+#[cfg(feature = "png")]
+pub(crate) fn write_png(path: &str, img: &BitMap) -> Result<()> {
+    let (y0, x0, height, width) = img.bounds();
+    let w = BufWriter::new(File::create(path)?);
+    let mut enc = png::Encoder::new(w, width as u32, height as u32);
+    enc.set_color(png::ColorType::Grayscale);
+    enc.set_depth(png::BitDepth::Eight);
+    let mut data = vec![255u8; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            if img.read_pixel(y0 + y as i32, x0 + x as i32) {
+                data[y * width + x] = 0
+            }
+        }
+    }
+    let mut w = enc.write_header().map_err(|e| Error::new(ErrorKind::Other, e))?;
+    w.write_image_data(data.as_slice()).map_err(|e| Error::new(ErrorKind::Other, e))
+}
+
+// pixels darker than mid-grey are lit, transparency is ignored
+#[cfg(feature = "png")]
+pub(crate) fn read_png(path: &str) -> Result<BitMap> {
+    let mut dec = png::Decoder::new(BufReader::new(File::open(path)?));
+    dec.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = dec.read_info().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let channels = info.color_type.samples();
+    let (width, height) = (info.width as usize, info.height as usize);
+    let mut img = BitMap::new(0, 0, height, width, false);
+    for y in 0..height {
+        let row = &buf[(y * info.line_size)..][..info.line_size];
+        for x in 0..width {
+            let px = &row[(x * channels)..][..channels];
+            let luma = if channels >= 3 {
+                (px[0] as u32 * 299 + px[1] as u32 * 587 + px[2] as u32 * 114) / 1000
+            } else {
+                px[0] as u32
+            };
+            if luma < 128 {
+                img.set_pixel(y as i32, x as i32, true)
+            }
+        }
+    }
+    Ok(img)
+}
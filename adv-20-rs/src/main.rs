@@ -1,6 +1,10 @@
 #![feature(str_internals)]
 extern crate owning_ref;
 
+mod bitmap;
+mod image_io;
+mod ca;
+
 use core::str::next_code_point;
 use std::collections::HashSet;
 use std::fs::File;
@@ -8,7 +12,11 @@ use std::io::{BufRead, BufReader};
 use std::iter::from_fn;
 use std::ops::Deref;
 use std::str::Chars;
+use std::thread::available_parallelism;
+use std::time::Instant;
 use owning_ref::{OwningHandle, OwningRef};
+use crate::bitmap::BitMap;
+use crate::ca::{Automaton, Rule};
 
 const FILENAME: &'static str = "i1.txt";
 
@@ -102,7 +110,7 @@ fn into_bool_array(s: impl Deref<Target=str>) -> Vec<bool> {
     s.chars().map(|c| c == '#').collect()
 }
 
-fn read_data() -> (Map, impl Fn(usize) -> bool) {
+fn read_data() -> (Map, impl Fn(usize) -> bool + Sync) {
     let mut it = read_lines();
     let table = into_bool_array(it.next().unwrap());
     it.next().unwrap();
@@ -136,14 +144,110 @@ fn number_in_area(img: &HashSet<(i32, i32)>, pos: (i32, i32)) -> usize {
     acc
 }
 
+fn bench(img: &Map, lookup: &(impl Fn(usize) -> bool + Sync), threads: usize) {
+    for n in [50, 500] {
+        // the hashset version takes minutes for the longer run
+        let sparse = (n <= 50).then(|| {
+            let start = Instant::now();
+            let mut sparse = Map {
+                ls: img.ls.clone(),
+                is_invert: img.is_invert
+            };
+            sparse.enhance_count(lookup, n);
+            (sparse.get_count(), start.elapsed())
+        });
+
+        let mut dense = BitMap::from_light_list(img.ls.iter().copied());
+        let start = Instant::now();
+        dense.enhance_count(lookup, n, 1);
+        let dense_t = start.elapsed();
+
+        let mut parallel = BitMap::from_light_list(img.ls.iter().copied());
+        let start = Instant::now();
+        parallel.enhance_count(lookup, n, threads);
+        let parallel_t = start.elapsed();
+
+        assert_eq!(dense, parallel);
+        if let Some((cnt, t)) = sparse {
+            assert_eq!(cnt, dense.get_count());
+            println!("{} iterations: hashset {:?}", n, t);
+        }
+        println!(
+            "{} iterations, {} lit: bitmap {:?}, bitmap x{} {:?}",
+            n, dense.get_count(), dense_t, threads, parallel_t
+        );
+    }
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
+}
+
 fn main() {
-    let (mut img, lookup) = read_data();
+    let (img, lookup) = read_data();
+    let threads = available_parallelism().map_or(1, |n| n.get());
+
+    if std::env::args().any(|a| a == "--bench") {
+        bench(&img, &lookup, threads);
+        return
+    }
+
+    let mut img = match arg_value("--load") {
+        #[cfg(feature = "png")]
+        Some(path) if path.ends_with(".png") => image_io::read_png(path.as_str()).unwrap(),
+        Some(path) => image_io::read_pbm(path.as_str()).unwrap(),
+        None => BitMap::from_light_list(img.ls.into_iter())
+    };
+
+    if std::env::args().any(|a| a == "--ca") {
+        let rule = match arg_value("--rule") {
+            None => Rule::from_fn(&lookup),
+            Some(r) => Rule::from_life_notation(r.as_str())
+                .or_else(|| Rule::from_table(r.as_str()))
+                .expect("bad rule")
+        };
+        let max_steps = arg_value("--steps").map_or(1000, |n| n.parse().unwrap());
+        let mut ca = Automaton::new(rule, img, threads);
+        match ca.find_cycle(max_steps) {
+            Some(c) if c.is_stable() => println!("stable from generation {}", c.start),
+            Some(c) => println!(
+                "period {} from generation {}, moving {:?} each period",
+                c.period, c.start, c.shift
+            ),
+            None => println!("no repeat within {} generations", max_steps)
+        }
+        // which generation the image written below shows
+        println!("stopped at generation {}", ca.generation());
+        if let Some(path) = arg_value("--pbm") {
+            image_io::write_pbm(path.as_str(), ca.image()).unwrap();
+        }
+        return
+    }
+
+    if let Some(n) = arg_value("--render") {
+        img.enhance_count(&lookup, n.parse().unwrap(), threads);
+        if let Some(path) = arg_value("--pbm") {
+            image_io::write_pbm(path.as_str(), &img).unwrap();
+        }
+        #[cfg(feature = "png")]
+        if let Some(path) = arg_value("--png") {
+            image_io::write_png(path.as_str(), &img).unwrap();
+        }
+        if img.is_invert() {
+            println!("infinitely many lit pixels");
+        } else {
+            println!("{} lit pixels", img.get_count());
+        }
+        return
+    }
 
-    img.enhance_count(&lookup, 2);
+    img.enhance_count(&lookup, 2, threads);
 
     println!("1> {}", img.get_count());
 
-    img.enhance_count(&lookup, 48);
+    img.enhance_count(&lookup, 48, threads);
 
     println!("2> {}", img.get_count());
 }
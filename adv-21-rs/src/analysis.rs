@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use crate::{dirac_outcome, Rules};
+
+fn expected_turns(lengths: &[crate::Weight]) -> f64 {
+    lengths
+        .iter()
+        .enumerate()
+        .map(|(turns, w)| turns as f64 * w.prob)
+        .sum()
+}
+
+pub(crate) fn report(starts: &[u32], rules: &Rules) {
+    let outcome = dirac_outcome(starts, rules);
+    for (idx, w) in outcome.wins.iter().enumerate() {
+        println!(
+            "player {} (start {}): wins in {} universes, probability {:.6}",
+            idx + 1, starts[idx], w.universes, w.prob
+        );
+    }
+    println!("expected game length: {:.3} turns", expected_turns(outcome.lengths.as_slice()));
+    println!("turns,universes,probability");
+    for (turns, w) in outcome.lengths.iter().enumerate() {
+        if w.universes != 0 {
+            println!("{},{},{:.6}", turns, w.universes, w.prob);
+        }
+    }
+}
+
+// outcome of a two player game for every pair of starting positions
+pub(crate) fn write_table(path: &str, rules: &Rules) -> Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(w, "p1_start,p2_start,p1_universes,p2_universes,p1_win_prob,p2_win_prob,expected_turns")?;
+    for a in 1..=rules.board {
+        for b in 1..=rules.board {
+            let outcome = dirac_outcome(&[a, b], rules);
+            writeln!(
+                w, "{},{},{},{},{:.6},{:.6},{:.3}",
+                a, b,
+                outcome.wins[0].universes, outcome.wins[1].universes,
+                outcome.wins[0].prob, outcome.wins[1].prob,
+                expected_turns(outcome.lengths.as_slice())
+            )?;
+        }
+    }
+    w.flush()
+}
@@ -1,10 +1,12 @@
+mod analysis;
+
 use std::collections::{HashMap, HashSet};
 use std::collections::hash_map::Entry;
 use std::fs::File;
 use std::hash::Hash;
 use std::io::{BufRead, BufReader};
 use std::iter::from_fn;
-use std::ops::Deref;
+use std::ops::{AddAssign, Deref, Mul};
 use std::str::Chars;
 
 const FILENAME: &'static str = "i1.txt";
@@ -25,9 +27,55 @@ fn read_lines() -> impl Iterator<Item=String> {
     })
 }
 
+#[derive(Copy, Clone, Debug)]
+struct Rules {
+    board: u32,
+    rolls_per_turn: u32,
+    // die and winning score for the deterministic game
+    det_sides: u32,
+    det_target: u32,
+    // die and winning score for the dirac game
+    dirac_sides: u32,
+    dirac_target: u32
+}
+
+impl Rules {
+    fn standard() -> Self {
+        Rules {
+            board: 10,
+            rolls_per_turn: 3,
+            det_sides: 100,
+            det_target: 1000,
+            dirac_sides: 3,
+            dirac_target: 21
+        }
+    }
+}
+
+// (total, number of ways) for every total of rolling a die with the given sides n times
+fn roll_distribution(sides: u32, n: u32) -> Vec<(u32, usize)> {
+    let mut ways = vec![1usize];
+    for _ in 0..n {
+        let mut next = vec![0; ways.len() + sides as usize];
+        for (total, &w) in ways.iter().enumerate() {
+            for face in 1..=sides as usize {
+                next[total + face] += w
+            }
+        }
+        ways = next;
+    }
+    ways
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, w)| w != 0)
+        .map(|(total, w)| (total as u32, w))
+        .collect()
+}
+
 struct DieP1 {
     inner: u32,
-    roll_cnt: u32
+    roll_cnt: u32,
+    sides: u32
 }
 
 impl DieP1 {
@@ -35,14 +83,15 @@ impl DieP1 {
         self.roll_cnt += 1;
         self.inner += 1;
         let r = self.inner;
-        self.inner %= 100;
+        self.inner %= self.sides;
         r
     }
 
-    fn new() -> Self {
+    fn new(sides: u32) -> Self {
         DieP1 {
             inner: 0,
-            roll_cnt: 0
+            roll_cnt: 0,
+            sides
         }
     }
 
@@ -55,17 +104,18 @@ fn parse_start(s: &str) -> u32 {
     s.rsplit_once(' ').unwrap().1.parse().unwrap()
 }
 
-fn read_data() -> (u32, u32) {
-    let mut it = read_lines();
-    let a = parse_start(it.next().unwrap().as_str());
-    let b = parse_start(it.next().unwrap().as_str());
-    (a, b)
+fn read_data() -> Vec<u32> {
+    read_lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_start(line.as_str()))
+        .collect()
 }
 
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+#[derive(Clone, Hash, Eq, PartialEq)]
 struct Game {
-    plays: [(u32, u32); 2],
-    is_p2_turn: bool
+    // (position, score) of each player
+    plays: Vec<(u32, u32)>,
+    turn: usize
 }
 
 struct Player {
@@ -81,37 +131,45 @@ impl Player {
         }
     }
 
-    fn turn(&mut self, n: u32) {
+    fn turn(&mut self, n: u32, board: u32) {
         self.pos += n;
-        self.pos %= 10;
+        self.pos %= board;
         self.score += self.pos + 1;
     }
 
-    fn is_winning(&self) -> bool {
-        self.score >= 1000
+    fn is_winning(&self, target: u32) -> bool {
+        self.score >= target
     }
 }
 
-fn p1(player_1: u32, player_2: u32) {
-    let mut die = DieP1::new();
-    let mut players = [
-        Player::new_with_pos(player_1),
-        Player::new_with_pos(player_2)
-    ];
+fn p1(starts: &[u32], rules: &Rules) {
+    let mut die = DieP1::new(rules.det_sides);
+    let mut players = starts
+        .iter()
+        .map(|&p| Player::new_with_pos(p))
+        .collect::<Vec<_>>();
 
-    let mut cur = false;
+    let mut cur = 0;
     loop {
-        players[cur as usize].turn(die.roll() + die.roll() + die.roll());
-        if players[cur as usize].is_winning() {
+        let roll = (0..rules.rolls_per_turn).map(|_| die.roll()).sum();
+        players[cur].turn(roll, rules.board);
+        if players[cur].is_winning(rules.det_target) {
             break
         }
-        cur = !cur;
+        cur = (cur + 1) % players.len();
     }
 
-    println!("1> {}", players[!cur as usize].score * die.roll_cnt)
+    let loser = players
+        .iter()
+        .enumerate()
+        .filter(|&(idx, _)| idx != cur)
+        .map(|(_, p)| p.score)
+        .min()
+        .unwrap();
+    println!("1> {}", loser * die.get_roll_count())
 }
 
-fn inc_hashmap<T: Eq + Hash>(hm: &mut HashMap<T, usize>, k: T, n: usize) {
+fn inc_hashmap<T: Eq + Hash, V: AddAssign>(hm: &mut HashMap<T, V>, k: T, n: V) {
     match hm.entry(k) {
         Entry::Occupied(mut o) => {
             *o.get_mut() += n;
@@ -122,55 +180,133 @@ fn inc_hashmap<T: Eq + Hash>(hm: &mut HashMap<T, usize>, k: T, n: usize) {
     }
 }
 
-fn p2(player_1: u32, player_2: u32) {
-    fn play_game_roll(mut g: Game, g_cnt: usize, win_counts: &mut [usize; 2], map: &mut HashMap<Game, usize>, roll: u32) {
-        let turn_idx = g.is_p2_turn as usize;
-        let cur_player = &mut g.plays[turn_idx];
-        cur_player.0 += roll;
-        cur_player.0 %= 10;
-        cur_player.1 += cur_player.0 + 1;
-        if cur_player.1 >= 21 {
-            win_counts[turn_idx] += g_cnt;
-        } else {
-            g.is_p2_turn ^= true;
-            inc_hashmap(map, g, g_cnt);
-        }
+// a number of universes, along with how likely ending up in one of them is
+#[derive(Copy, Clone, Default, Debug)]
+struct Weight {
+    universes: u128,
+    prob: f64
+}
+
+impl AddAssign for Weight {
+    fn add_assign(&mut self, rhs: Self) {
+        self.universes += rhs.universes;
+        self.prob += rhs.prob;
     }
+}
+
+impl Mul for Weight {
+    type Output = Self;
 
-    fn play_game(g: Game, g_cnt: usize, win_counts: &mut [usize; 2], map: &mut HashMap<Game, usize>) {
-        for (roll, cnt) in [
-            (3, 1),
-            (4, 3),
-            (5, 6),
-            (6, 7),
-            (7, 6),
-            (8, 3),
-            (9, 1)
-        ] {
-            play_game_roll(g, g_cnt * cnt, win_counts, map, roll)
+    fn mul(self, rhs: Self) -> Self::Output {
+        Weight {
+            universes: self.universes * rhs.universes,
+            prob: self.prob * rhs.prob
         }
     }
+}
+
+struct Outcome {
+    // universes each player wins in, which overflows u64 with more than two players
+    wins: Vec<Weight>,
+    // games ending after each number of turns, counting every player's turn
+    lengths: Vec<Weight>
+}
+
+fn dirac_outcome(starts: &[u32], rules: &Rules) -> Outcome {
+    let dist = roll_distribution(rules.dirac_sides, rules.rolls_per_turn);
+    let total_ways = dist.iter().map(|&(_, w)| w).sum::<usize>() as f64;
+    let dist = dist
+        .into_iter()
+        .map(|(roll, w)| (roll, Weight { universes: w as u128, prob: w as f64 / total_ways }))
+        .collect::<Vec<_>>();
 
     let mut games_ls = HashMap::new();
-    let mut win_counts = [0; 2];
+    let mut wins = vec![Weight::default(); starts.len()];
+    let mut lengths = vec![Weight::default()];
     games_ls.insert(Game {
-        plays: [(player_1 - 1, 0), (player_2 - 1, 0)],
-        is_p2_turn: false
-    }, 1);
+        plays: starts.iter().map(|&p| (p - 1, 0)).collect(),
+        turn: 0
+    }, Weight { universes: 1, prob: 1.0 });
     while games_ls.len() != 0 {
         let mut new_games_ls = HashMap::new();
-        for (game, cnt) in games_ls.drain() {
-            play_game(game, cnt, &mut win_counts, &mut new_games_ls);
+        let mut ended = Weight::default();
+        for (game, g_cnt) in games_ls.drain() {
+            for &(roll, cnt) in dist.iter() {
+                let mut g = game.clone();
+                let cur_player = &mut g.plays[g.turn];
+                cur_player.0 += roll;
+                cur_player.0 %= rules.board;
+                cur_player.1 += cur_player.0 + 1;
+                if cur_player.1 >= rules.dirac_target {
+                    wins[g.turn] += g_cnt * cnt;
+                    ended += g_cnt * cnt;
+                } else {
+                    g.turn = (g.turn + 1) % starts.len();
+                    inc_hashmap(&mut new_games_ls, g, g_cnt * cnt);
+                }
+            }
         }
+        lengths.push(ended);
         games_ls = new_games_ls;
     }
+    Outcome {
+        wins,
+        lengths
+    }
+}
+
+fn p2(starts: &[u32], rules: &Rules) {
+    let outcome = dirac_outcome(starts, rules);
+    println!("2> {}", outcome.wins.iter().map(|w| w.universes).max().unwrap())
+}
 
-    println!("2> {}", win_counts.into_iter().max().unwrap())
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
 }
 
 fn main() {
-    let (player_one, player_two) = read_data();
+    let starts = arg_value("--players")
+        .map(|s| s.split(',').map(|v| v.parse().unwrap()).collect::<Vec<_>>())
+        .unwrap_or_else(read_data);
 
-    p1(player_one, player_two);
-    p2(player_one, player_two);
+    let std_rules = Rules::standard();
+    let arg_or = |name, default| arg_value(name).map_or(default, |v| v.parse().unwrap());
+    let rules = Rules {
+        board: arg_or("--board", std_rules.board),
+        rolls_per_turn: arg_or("--rolls", std_rules.rolls_per_turn),
+        det_sides: arg_or("--det-sides", std_rules.det_sides),
+        det_target: arg_or("--det-target", std_rules.det_target),
+        dirac_sides: arg_or("--dirac-sides", std_rules.dirac_sides),
+        dirac_target: arg_or("--dirac-target", std_rules.dirac_target)
+    };
+    if starts.is_empty() || rules.board == 0 {
+        eprintln!("need at least one player and a board of at least one square");
+        std::process::exit(2)
+    }
+    if rules.rolls_per_turn == 0 || rules.det_sides == 0 || rules.dirac_sides == 0 {
+        eprintln!("need at least one roll per turn of dice with at least one side");
+        std::process::exit(2)
+    }
+    if let Some(&p) = starts.iter().find(|&&p| p < 1 || p > rules.board) {
+        eprintln!("starting position {} is off the board, which has squares 1 to {}", p, rules.board);
+        std::process::exit(2)
+    }
+
+    if std::env::args().any(|a| a == "--analyse") {
+        analysis::report(starts.as_slice(), &rules);
+        if let Some(path) = arg_value("--csv") {
+            analysis::write_table(path.as_str(), &rules).unwrap();
+        }
+        return
+    }
+
+    // part 1 scores the loser, and a lone player never loses
+    if starts.len() > 1 {
+        p1(starts.as_slice(), &rules);
+    } else {
+        eprintln!("skipping part 1, which needs at least two players");
+    }
+    p2(starts.as_slice(), &rules);
 }
@@ -0,0 +1,219 @@
+// inclusive ranges along each axis
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct Cuboid<const N: usize> {
+    pub(crate) ranges: [(i32, i32); N]
+}
+
+fn intersect_1d(a: (i32, i32), b: (i32, i32)) -> Option<(i32, i32)> {
+    let v = (a.0.max(b.0), a.1.min(b.1));
+    if v.0 > v.1 {
+        None
+    } else {
+        Some(v)
+    }
+}
+
+impl<const N: usize> Cuboid<N> {
+    pub(crate) fn new(ranges: [(i32, i32); N]) -> Self {
+        Cuboid {
+            ranges
+        }
+    }
+
+    pub(crate) fn volume(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|r| (r.1 - r.0) as u64 + 1)
+            .product()
+    }
+
+    pub(crate) fn contains(&self, p: &[i32; N]) -> bool {
+        self.ranges
+            .iter()
+            .zip(p.iter())
+            .all(|(r, v)| (r.0..=r.1).contains(v))
+    }
+
+    pub(crate) fn intersect(&self, oth: &Self) -> Option<Self> {
+        let mut ranges = self.ranges;
+        for i in 0..N {
+            ranges[i] = intersect_1d(self.ranges[i], oth.ranges[i])?;
+        }
+        Some(Cuboid {
+            ranges
+        })
+    }
+
+    // smallest cuboid containing both
+    pub(crate) fn hull(&self, oth: &Self) -> Self {
+        let mut ranges = self.ranges;
+        for i in 0..N {
+            ranges[i] = (self.ranges[i].0.min(oth.ranges[i].0), self.ranges[i].1.max(oth.ranges[i].1))
+        }
+        Cuboid {
+            ranges
+        }
+    }
+
+    // splits off the parts of self outside of rem, at most two per axis
+    pub(crate) fn without(&self, rem: &Self) -> Vec<Self> {
+        let rem = match self.intersect(rem) {
+            Some(v) => v,
+            None => return vec![*self]
+        };
+        let mut ls = Vec::new();
+        let mut a = *self;
+        for i in 0..N {
+            if a.ranges[i].0 < rem.ranges[i].0 {
+                let mut piece = a;
+                piece.ranges[i] = (a.ranges[i].0, rem.ranges[i].0 - 1);
+                ls.push(piece);
+                a.ranges[i].0 = rem.ranges[i].0;
+            }
+            if a.ranges[i].1 > rem.ranges[i].1 {
+                let mut piece = a;
+                piece.ranges[i] = (rem.ranges[i].1 + 1, a.ranges[i].1);
+                ls.push(piece);
+                a.ranges[i].1 = rem.ranges[i].1;
+            }
+        }
+        ls
+    }
+}
+
+// set of points, stored as disjoint cuboids
+#[derive(Clone, Debug)]
+pub(crate) struct CuboidSet<const N: usize> {
+    boxes: Vec<Cuboid<N>>
+}
+
+impl<const N: usize> CuboidSet<N> {
+    pub(crate) fn new() -> Self {
+        CuboidSet {
+            boxes: Vec::new()
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item=&Cuboid<N>> {
+        self.boxes.iter()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.boxes.len()
+    }
+
+    pub(crate) fn insert(&mut self, c: &Cuboid<N>) {
+        self.remove(c);
+        self.boxes.push(*c);
+    }
+
+    pub(crate) fn remove(&mut self, c: &Cuboid<N>) {
+        self.boxes = self.boxes
+            .iter()
+            .flat_map(|b| b.without(c))
+            .collect();
+    }
+
+    pub(crate) fn union(&self, oth: &Self) -> Self {
+        let mut ret = self.clone();
+        for c in oth.iter() {
+            ret.insert(c)
+        }
+        ret
+    }
+
+    pub(crate) fn intersection(&self, oth: &Self) -> Self {
+        let mut boxes = Vec::new();
+        for a in self.iter() {
+            for b in oth.iter() {
+                boxes.extend(a.intersect(b))
+            }
+        }
+        CuboidSet {
+            boxes
+        }
+    }
+
+    pub(crate) fn difference(&self, oth: &Self) -> Self {
+        let mut ret = self.clone();
+        for c in oth.iter() {
+            ret.remove(c)
+        }
+        ret
+    }
+
+    pub(crate) fn contains(&self, p: &[i32; N]) -> bool {
+        self.boxes.iter().any(|c| c.contains(p))
+    }
+
+    pub(crate) fn bounding_box(&self) -> Option<Cuboid<N>> {
+        let mut it = self.boxes.iter();
+        let first = *it.next()?;
+        Some(it.fold(first, |acc, c| acc.hull(c)))
+    }
+
+    pub(crate) fn volume(&self) -> u64 {
+        self.boxes.iter().map(|c| c.volume()).sum()
+    }
+
+    pub(crate) fn volume_within(&self, region: &Cuboid<N>) -> u64 {
+        self.boxes
+            .iter()
+            .filter_map(|c| c.intersect(region))
+            .map(|c| c.volume())
+            .sum()
+    }
+}
+
+impl<const N: usize> From<Cuboid<N>> for CuboidSet<N> {
+    fn from(c: Cuboid<N>) -> Self {
+        CuboidSet {
+            boxes: vec![c]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(boxes: &[[(i32, i32); 2]]) -> CuboidSet<2> {
+        let mut ret = CuboidSet::new();
+        for b in boxes {
+            ret.insert(&Cuboid::new(*b))
+        }
+        ret
+    }
+
+    // points of the 12x12 grid around the test boxes for which f holds
+    fn count(f: impl Fn(&[i32; 2]) -> bool) -> u64 {
+        (-1..11).flat_map(|x| (-1..11).map(move |y| [x, y])).filter(|p| f(p)).count() as u64
+    }
+
+    #[test]
+    fn set_ops_match_brute_force() {
+        let a = set(&[[(0, 4), (0, 4)], [(3, 8), (2, 6)], [(6, 9), (7, 9)]]);
+        let b = set(&[[(2, 6), (1, 9)], [(0, 1), (8, 9)]]);
+        assert_eq!(a.volume(), count(|p| a.contains(p)));
+        assert_eq!(b.volume(), count(|p| b.contains(p)));
+        let u = a.union(&b);
+        let i = a.intersection(&b);
+        let d = a.difference(&b);
+        assert_eq!(u.volume(), count(|p| a.contains(p) || b.contains(p)));
+        assert_eq!(i.volume(), count(|p| a.contains(p) && b.contains(p)));
+        assert_eq!(d.volume(), count(|p| a.contains(p) && !b.contains(p)));
+        assert_eq!(d.volume(), count(|p| d.contains(p)));
+        assert_eq!(u.bounding_box(), Some(Cuboid::new([(0, 9), (0, 9)])));
+        assert_eq!(i.bounding_box(), Some(Cuboid::new([(2, 6), (1, 9)])));
+        assert_eq!(CuboidSet::<2>::new().bounding_box(), None);
+    }
+
+    #[test]
+    fn disjoint_difference_is_unchanged() {
+        let a = set(&[[(0, 2), (0, 2)]]);
+        let b = set(&[[(5, 6), (5, 6)]]);
+        assert_eq!(a.difference(&b).volume(), 9);
+        assert_eq!(a.intersection(&b).volume(), 0);
+        assert_eq!(a.union(&b).volume(), 13);
+    }
+}
@@ -1,201 +1,140 @@
-#![feature(linked_list_cursors)]
-
-use std::collections::{HashMap, HashSet, LinkedList};
-use std::collections::hash_map::Entry;
-use std::fs::File;
-use std::hash::Hash;
-use std::io::{BufRead, BufReader};
-use std::iter::from_fn;
-use std::ops::{Deref, RangeInclusive, RangeToInclusive};
-use std::rc::{Rc, Weak};
-use std::str::Chars;
+mod cuboid;
+// shared between days, which each only use part of it
+#[allow(dead_code)]
+#[path = "../../common/lexer.rs"]
+mod lexer;
+mod solver;
+mod snapshot;
+
+use crate::cuboid::Cuboid;
+use crate::lexer::{LexError, LexErrorKind, Lexer};
+use crate::solver::Solver;
 
 const FILENAME: &'static str = "i1.txt";
 
-fn read_lines() -> impl Iterator<Item=String> {
-    let file = File::open(FILENAME).unwrap();
-    let mut buf = BufReader::new(file);
-    from_fn(move || {
-        let mut s = String::new();
-        if buf.read_line(&mut s).unwrap() == 0 {
-            None
-        } else {
-            if s.ends_with('\n') {
-                s.pop();
-            }
-            Some(s)
-        }
-    })
-}
-
-fn parse_line(s: &str) -> (bool, Rect) {
-    fn parse_eq(s: &str) -> (i32, i32) {
-        let s = &s[2..];
-        let (a, b) = s.split_once("..").unwrap();
-        (a.parse().unwrap(), b.parse().unwrap())
+// x=a..b,y=c..d,z=e..f
+fn parse_rect(lex: &mut Lexer) -> Result<Rect, LexError> {
+    fn bound(lex: &mut Lexer) -> Result<i32, LexError> {
+        let (n, span) = lex.expect_number()?;
+        n.try_into().map_err(|_| LexError { span, kind: LexErrorKind::Overflow })
     }
 
-    let (state, s) = if s.starts_with("on ") {
-        (true, &s[3..])
-    } else if s.starts_with("off ") {
-        (false, &s[4..])
-    } else {
-        panic!("parse failure")
-    };
-    let (x, yz) = s.split_once(',').unwrap();
-    let (y, z) = yz.split_once(',').unwrap();
-    let x = parse_eq(x);
-    let y = parse_eq(y);
-    let z = parse_eq(z);
-    (
-        state,
-        Rect {
-            x,
-            y,
-            z
+    let mut ranges = [(0, 0); 3];
+    for (i, axis) in ["x", "y", "z"].into_iter().enumerate() {
+        if i != 0 {
+            lex.expect_punct(',')?;
         }
-    )
-}
-
-fn read_data() -> impl Iterator<Item=(bool, Rect)> {
-    read_lines().map(|line| {
-        parse_line(line.as_str())
-    })
-}
-
-#[derive(Copy, Clone)]
-struct Rect {
-    x: (i32, i32),
-    y: (i32, i32),
-    z: (i32, i32)
-}
-
-impl Rect {
-    fn volume(&self) -> u64 {
-        let x = (self.x.1 - self.x.0) as u64 + 1;
-        let y = (self.y.1 - self.y.0) as u64 + 1;
-        let z = (self.z.1 - self.z.0) as u64 + 1;
-        x * y * z
+        let (name, span) = lex.expect_ident()?;
+        if name != axis {
+            return Err(LexError { span, kind: LexErrorKind::Expected(axis) })
+        }
+        lex.expect_punct('=')?;
+        let lo = bound(lex)?;
+        lex.expect_punct('.')?;
+        lex.expect_punct('.')?;
+        ranges[i] = (lo, bound(lex)?);
     }
+    Ok(Rect::new(ranges))
 }
 
-fn intersect_1d(a: (i32, i32), b: (i32, i32)) -> Option<(i32, i32)> {
-    let v = (a.0.max(b.0), a.1.min(b.1));
-    if v.0 > v.1 {
-        None
-    } else {
-        Some(v)
+fn parse_region(s: &str) -> Result<Rect, LexError> {
+    let mut lex = Lexer::new(s);
+    let ret = parse_rect(&mut lex)?;
+    match lex.next() {
+        None => Ok(ret),
+        Some(Ok(t)) => Err(LexError { span: t.span, kind: LexErrorKind::Expected("end of input") }),
+        Some(Err(e)) => Err(e)
     }
 }
 
-fn intersect(a: &Rect, b: &Rect) -> Option<Rect> {
-    Some(Rect {
-        x: intersect_1d(a.x, b.x)?,
-        y: intersect_1d(a.y, b.y)?,
-        z: intersect_1d(a.z, b.z)?
-    })
-}
-
-fn without(a: &Rect, rem: &Rect) -> Vec<Rect> {
-    let rem = match intersect(a, rem) {
-        Some(v) => v,
-        None => return vec![*a]
+// on|off x=a..b,y=c..d,z=e..f, alone on its line
+fn parse_step(lex: &mut Lexer) -> Result<(bool, Rect), LexError> {
+    let (state, span) = lex.expect_ident()?;
+    let state = match state {
+        "on" => true,
+        "off" => false,
+        _ => return Err(LexError { span, kind: LexErrorKind::Expected("on or off") })
     };
-    let mut ls = Vec::new();
-    let mut a = *a;
-    if a.x.0 < rem.x.0 {
-        ls.push(Rect {
-            x: (a.x.0, rem.x.0 - 1),
-            y: a.y,
-            z: a.z
-        });
-        a.x.0 = rem.x.0;
-    }
-    if a.x.1 > rem.x.1 {
-        ls.push(Rect {
-            x: (rem.x.1 + 1, a.x.1),
-            y: a.y,
-            z: a.z
-        });
-        a.x.1 = rem.x.1;
-    }
-    if a.y.0 < rem.y.0 {
-        ls.push(Rect {
-            x: a.x,
-            y: (a.y.0, rem.y.0 - 1),
-            z: a.z
-        });
-        a.y.0 = rem.y.0;
-    }
-    if a.y.1 > rem.y.1 {
-        ls.push(Rect {
-            x: a.x,
-            y: (rem.y.1 + 1, a.y.1),
-            z: a.z
-        });
-        a.y.1 = rem.y.1;
-    }
-    if a.z.0 < rem.z.0 {
-        ls.push(Rect {
-            x: a.x,
-            y: a.y,
-            z: (a.z.0, rem.z.0 - 1)
-        });
-        a.z.0 = rem.z.0;
-    }
-    if a.z.1 > rem.z.1 {
-        ls.push(Rect {
-            x: a.x,
-            y: a.y,
-            z: (rem.z.1 + 1, a.z.1)
-        });
-    }
-    ls
-}
-
-fn filter_p1(a: &Rect) -> Option<Rect> {
-    intersect(a, &Rect {
-        x: (-50, 50),
-        y: (-50, 50),
-        z: (-50, 50)
-    })
-}
-
-fn remove_intersecting(ls: &mut LinkedList<Rect>, rem: &Rect) {
-    let mut cursor = ls.cursor_front_mut();
-    loop {
-        let new_rects = if let Some(cur) = cursor.current() {
-            without(cur, rem)
-        } else {
-            break
-        };
-        cursor.remove_current();
-        for a in new_rects {
-            cursor.insert_before(a);
+    let rect = parse_rect(lex)?;
+    match lex.peek() {
+        Some(Ok(t)) if t.span.line == span.line => {
+            Err(LexError { span: t.span, kind: LexErrorKind::Expected("end of line") })
         }
+        Some(Err(_)) => Err(lex.next().unwrap().unwrap_err()),
+        _ => Ok((state, rect))
     }
 }
 
-fn set_intersecting(ls: &mut LinkedList<Rect>, r: (bool, Rect)) {
-    remove_intersecting(ls, &r.1);
-    if r.0 {
-        ls.push_front(r.1)
+fn read_data() -> Vec<(bool, Rect)> {
+    let src = std::fs::read_to_string(FILENAME).unwrap();
+    let mut lex = Lexer::new(src.as_str());
+    let mut ret = Vec::new();
+    while !lex.is_done() {
+        ret.push(parse_step(&mut lex).unwrap_or_else(|e| panic!("{}: {}", FILENAME, e)))
     }
+    ret
 }
 
-fn volume_within_p1(rect: &Rect) -> u64 {
-    filter_p1(rect).map(|v| v.volume()).unwrap_or(0)
+type Rect = Cuboid<3>;
+
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
 }
 
 fn main() {
-    let mut p1_ls = LinkedList::new();
+    let steps = read_data();
+
+    let p1_region = Rect::new([(-50, 50); 3]);
+    let p2_region = steps
+        .iter()
+        .map(|(_, c)| *c)
+        .reduce(|a, b| a.hull(&b))
+        .unwrap();
+
+    if std::env::args().any(|a| a == "--bench") {
+        solver::bench(steps.as_slice(), &[("p1", p1_region), ("p2", p2_region)]);
+        return
+    }
 
-    for (state, rect) in read_data() {
-        set_intersecting(&mut p1_ls, (state, rect));
+    let region = arg_value("--region").map_or(p1_region, |s| {
+        parse_region(s.as_str()).unwrap_or_else(|e| panic!("bad --region: {}", e))
+    });
+    // replaying the steps is only worth it for the queries which look at the result
+    let queries = ["--snapshots", "--compare", "--point", "--json", "--obj"];
+    if std::env::args().any(|a| queries.contains(&a.as_str())) {
+        let (snaps, reactor) = snapshot::snapshots(steps.as_slice(), &region);
+        if std::env::args().any(|a| a == "--snapshots") {
+            for (idx, snap) in snaps.iter().enumerate() {
+                println!(
+                    "{:>4} {:<3} lit {} in region {} ({} cuboids)",
+                    idx + 1, if steps[idx].0 { "on" } else { "off" },
+                    snap.lit, snap.lit_in_region, snap.pieces
+                );
+            }
+        }
+        if let Some(n) = arg_value("--compare") {
+            snapshot::compare(steps.as_slice(), n.parse().unwrap(), &reactor);
+        }
+        if let Some(p) = arg_value("--point") {
+            let p = p.split(',').map(|v| v.parse().unwrap()).collect::<Vec<i32>>();
+            let p = [p[0], p[1], p[2]];
+            println!("{},{},{} is {} at the end", p[0], p[1], p[2], if reactor.contains(&p) { "on" } else { "off" });
+        }
+        if let Some(path) = arg_value("--json") {
+            snapshot::write_json(path.as_str(), &reactor).unwrap();
+        }
+        if let Some(path) = arg_value("--obj") {
+            snapshot::write_obj(path.as_str(), &reactor).unwrap();
+        }
     }
 
-    let res_p1 = p1_ls.iter().map(|r| volume_within_p1(r)).sum::<u64>();
-    let res_p2 = p1_ls.iter().map(|r| r.volume()).sum::<u64>();
+    let solver = arg_value("--solver")
+        .map_or(Solver::Fragment, |s| Solver::from_name(s.as_str()).expect("unknown solver"));
+
+    let res_p1 = solver.solve(steps.as_slice(), &p1_region).0;
+    let res_p2 = solver.solve(steps.as_slice(), &p2_region).0;
     println!("1> {}", res_p1);
     println!("2> {}", res_p2);
 }
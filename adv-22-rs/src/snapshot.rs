@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use crate::cuboid::CuboidSet;
+use crate::Rect;
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Snapshot {
+    pub(crate) lit: u64,
+    pub(crate) lit_in_region: u64,
+    pub(crate) pieces: usize
+}
+
+// state of the reactor after every step, and the final disjoint cuboids
+pub(crate) fn snapshots(steps: &[(bool, Rect)], region: &Rect) -> (Vec<Snapshot>, CuboidSet<3>) {
+    let mut reactor = CuboidSet::new();
+    let mut ret = Vec::with_capacity(steps.len());
+    for (state, c) in steps {
+        if *state {
+            reactor.insert(c)
+        } else {
+            reactor.remove(c)
+        }
+        ret.push(Snapshot {
+            lit: reactor.volume(),
+            lit_in_region: reactor.volume_within(region),
+            pieces: reactor.len()
+        });
+    }
+    (ret, reactor)
+}
+
+pub(crate) fn write_json(path: &str, reactor: &CuboidSet<3>) -> Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(w, "[")?;
+    for (idx, c) in reactor.iter().enumerate() {
+        let [x, y, z] = c.ranges;
+        writeln!(
+            w, "  {{\"x\": [{},{}], \"y\": [{},{}], \"z\": [{},{}]}}{}",
+            x.0, x.1, y.0, y.1, z.0, z.1,
+            if idx + 1 == reactor.len() { "" } else { "," }
+        )?;
+    }
+    writeln!(w, "]")?;
+    w.flush()
+}
+
+// each cuboid as a closed box, covering whole cubes so neighbours share faces
+pub(crate) fn write_obj(path: &str, reactor: &CuboidSet<3>) -> Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    for (idx, c) in reactor.iter().enumerate() {
+        let [x, y, z] = c.ranges;
+        writeln!(w, "o cuboid{}", idx)?;
+        for i in 0..8 {
+            writeln!(
+                w, "v {} {} {}",
+                if i & 1 == 0 { x.0 } else { x.1 + 1 },
+                if i & 2 == 0 { y.0 } else { y.1 + 1 },
+                if i & 4 == 0 { z.0 } else { z.1 + 1 }
+            )?;
+        }
+        let base = idx * 8 + 1;
+        for face in [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]] {
+            writeln!(
+                w, "f {} {} {} {}",
+                base + face[0], base + face[1], base + face[2], base + face[3]
+            )?;
+        }
+    }
+    w.flush()
+}
+
+fn describe_box(c: Option<Rect>) -> String {
+    match c {
+        Some(c) => format!(
+            "x={}..{},y={}..{},z={}..{}",
+            c.ranges[0].0, c.ranges[0].1, c.ranges[1].0, c.ranges[1].1, c.ranges[2].0, c.ranges[2].1
+        ),
+        None => "empty".to_string()
+    }
+}
+
+// how the cubes lit after the first n steps differ from those lit at the end
+pub(crate) fn compare(steps: &[(bool, Rect)], n: usize, reactor: &CuboidSet<3>) {
+    let mut before = CuboidSet::new();
+    for (state, c) in steps.iter().take(n) {
+        if *state {
+            before.insert(c)
+        } else {
+            before.remove(c)
+        }
+    }
+    let rows = [
+        (format!("lit after step {}", n), before.clone()),
+        ("lit at the end".to_string(), reactor.clone()),
+        ("lit at both".to_string(), before.intersection(reactor)),
+        ("lit at either".to_string(), before.union(reactor)),
+        ("turned off since".to_string(), before.difference(reactor)),
+        ("turned on since".to_string(), reactor.difference(&before))
+    ];
+    for (name, set) in rows.iter() {
+        println!("{:<20} {:>16}  within {}", name, set.volume(), describe_box(set.bounding_box()));
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use crate::cuboid::CuboidSet;
+use crate::Rect;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Solver {
+    // disjoint cuboids, split up on every overlap
+    Fragment,
+    // inclusion-exclusion over signed, possibly overlapping cuboids
+    Signed,
+    // x slabs between step boundaries, each a compressed y/z bitmap
+    Compress
+}
+
+impl Solver {
+    pub(crate) const ALL: [Solver; 3] = [Solver::Fragment, Solver::Signed, Solver::Compress];
+
+    pub(crate) fn from_name(s: &str) -> Option<Self> {
+        Some(match s {
+            "fragment" => Solver::Fragment,
+            "signed" => Solver::Signed,
+            "compress" => Solver::Compress,
+            _ => None?
+        })
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Solver::Fragment => "fragment",
+            Solver::Signed => "signed",
+            Solver::Compress => "compress"
+        }
+    }
+
+    // lit volume within region once all the steps have run, along with the
+    // number of cuboids (or compressed cells) the solver ended up with
+    pub(crate) fn solve(&self, steps: &[(bool, Rect)], region: &Rect) -> (u64, usize) {
+        let steps = steps
+            .iter()
+            .filter_map(|(state, c)| Some((*state, c.intersect(region)?)))
+            .collect::<Vec<_>>();
+        match self {
+            Solver::Fragment => solve_fragment(steps.as_slice()),
+            Solver::Signed => solve_signed(steps.as_slice()),
+            Solver::Compress => solve_compress(steps.as_slice())
+        }
+    }
+}
+
+fn solve_fragment(steps: &[(bool, Rect)]) -> (u64, usize) {
+    let mut reactor = CuboidSet::new();
+    for (state, c) in steps {
+        if *state {
+            reactor.insert(c)
+        } else {
+            reactor.remove(c)
+        }
+    }
+    (reactor.volume(), reactor.len())
+}
+
+fn solve_signed(steps: &[(bool, Rect)]) -> (u64, usize) {
+    let mut signed: HashMap<Rect, i64> = HashMap::new();
+    for (state, c) in steps {
+        let mut update = HashMap::new();
+        for (e, n) in signed.iter() {
+            if let Some(i) = e.intersect(c) {
+                *update.entry(i).or_insert(0) -= n
+            }
+        }
+        if *state {
+            *update.entry(*c).or_insert(0) += 1
+        }
+        for (e, n) in update {
+            *signed.entry(e).or_insert(0) += n
+        }
+        signed.retain(|_, n| *n != 0);
+    }
+    let v = signed
+        .iter()
+        .map(|(c, n)| c.volume() as i64 * n)
+        .sum::<i64>();
+    (v as u64, signed.len())
+}
+
+fn compress_axis(steps: &[&(bool, Rect)], axis: usize) -> Vec<i32> {
+    let mut ret = steps
+        .iter()
+        .flat_map(|(_, c)| [c.ranges[axis].0, c.ranges[axis].1 + 1])
+        .collect::<Vec<_>>();
+    ret.sort();
+    ret.dedup();
+    ret
+}
+
+// sets or clears bits [from, to)
+fn set_bits(row: &mut [u64], from: usize, to: usize, state: bool) {
+    let mut i = from;
+    while i < to {
+        let word = i / 64;
+        let end = to.min((word + 1) * 64);
+        let len = end - i;
+        let mask = if len == 64 { !0 } else { ((1u64 << len) - 1) << (i % 64) };
+        if state {
+            row[word] |= mask
+        } else {
+            row[word] &= !mask
+        }
+        i = end;
+    }
+}
+
+fn solve_compress(steps: &[(bool, Rect)]) -> (u64, usize) {
+    let all = steps.iter().collect::<Vec<_>>();
+    let xs = compress_axis(all.as_slice(), 0);
+    let mut total = 0;
+    let mut cells = 0;
+    for w in xs.windows(2) {
+        let active = steps
+            .iter()
+            .filter(|(_, c)| (c.ranges[0].0..=c.ranges[0].1).contains(&w[0]))
+            .collect::<Vec<_>>();
+        if !active.iter().any(|(state, _)| *state) {
+            continue
+        }
+        let ys = compress_axis(active.as_slice(), 1);
+        let zs = compress_axis(active.as_slice(), 2);
+        let words = (zs.len() + 63) / 64;
+        let mut grid = vec![0u64; ys.len() * words];
+        for (state, c) in active {
+            let y0 = ys.binary_search(&c.ranges[1].0).unwrap();
+            let y1 = ys.binary_search(&(c.ranges[1].1 + 1)).unwrap();
+            let z0 = zs.binary_search(&c.ranges[2].0).unwrap();
+            let z1 = zs.binary_search(&(c.ranges[2].1 + 1)).unwrap();
+            for y in y0..y1 {
+                set_bits(&mut grid[(y * words)..][..words], z0, z1, *state)
+            }
+        }
+        let mut area = 0;
+        for y in 0..(ys.len() - 1) {
+            let row = &grid[(y * words)..][..words];
+            for z in 0..(zs.len() - 1) {
+                if (row[z / 64] >> (z % 64)) & 1 != 0 {
+                    area += (ys[y + 1] - ys[y]) as u64 * (zs[z + 1] - zs[z]) as u64
+                }
+            }
+        }
+        total += area * (w[1] - w[0]) as u64;
+        cells += (ys.len() - 1) * (zs.len() - 1);
+    }
+    (total, cells)
+}
+
+pub(crate) fn bench(steps: &[(bool, Rect)], regions: &[(&str, Rect)]) {
+    for (name, region) in regions {
+        let mut expected = None;
+        for solver in Solver::ALL {
+            let start = Instant::now();
+            let (v, n) = solver.solve(steps, region);
+            let t = start.elapsed();
+            println!("{} {:>8}: volume {} with {} pieces in {:?}", name, solver.name(), v, n, t);
+            match expected {
+                None => expected = Some(v),
+                Some(e) => assert_eq!(e, v, "{} disagrees on {}", solver.name(), name)
+            }
+        }
+    }
+}
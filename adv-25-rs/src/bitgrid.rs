@@ -0,0 +1,205 @@
+use crate::{Grid, Space};
+
+// the wrapping grid as one bitset per row for each herd
+pub(crate) struct BitGrid {
+    east: Vec<u64>,
+    south: Vec<u64>,
+    width: usize,
+    height: usize,
+    // u64 words per row
+    stride: usize
+}
+
+// bit x of out is bit (x + 1) % width of row
+fn rotate_from_right(row: &[u64], out: &mut [u64], width: usize) {
+    let n = row.len();
+    for i in 0..n {
+        out[i] = (row[i] >> 1) | if i + 1 < n { row[i + 1] << 63 } else { 0 };
+    }
+    let last = width - 1;
+    out[last / 64] &= !(1 << (last % 64));
+    out[last / 64] |= (row[0] & 1) << (last % 64);
+}
+
+// bit x of out is bit (x - 1) % width of row
+fn rotate_from_left(row: &[u64], out: &mut [u64], width: usize) {
+    let n = row.len();
+    for i in (0..n).rev() {
+        out[i] = (row[i] << 1) | if i > 0 { row[i - 1] >> 63 } else { 0 };
+    }
+    let last = width - 1;
+    out[0] |= (row[last / 64] >> (last % 64)) & 1;
+    if width % 64 != 0 {
+        out[n - 1] &= (1 << (width % 64)) - 1
+    }
+}
+
+impl BitGrid {
+    pub(crate) fn from_grid(g: &Grid) -> Self {
+        let stride = (g.width + 63) / 64;
+        let mut east = vec![0; stride * g.height];
+        let mut south = vec![0; stride * g.height];
+        for y in 0..g.height {
+            for x in 0..g.width {
+                let bit = 1 << (x % 64);
+                match g.data[y * g.width + x] {
+                    Space::Right => east[y * stride + x / 64] |= bit,
+                    Space::Down => south[y * stride + x / 64] |= bit,
+                    Space::Empty => ()
+                }
+            }
+        }
+        BitGrid {
+            east,
+            south,
+            width: g.width,
+            height: g.height,
+            stride
+        }
+    }
+
+    pub(crate) fn to_grid(&self) -> Grid {
+        let mut data = vec![Space::Empty; self.width * self.height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let idx = y * self.stride + x / 64;
+                let bit = 1 << (x % 64);
+                if self.east[idx] & bit != 0 {
+                    data[y * self.width + x] = Space::Right
+                } else if self.south[idx] & bit != 0 {
+                    data[y * self.width + x] = Space::Down
+                }
+            }
+        }
+        Grid {
+            data,
+            back: vec![Space::Empty; self.width * self.height],
+            width: self.width,
+            height: self.height
+        }
+    }
+
+    fn tick_east(&mut self) -> usize {
+        let s = self.stride;
+        let mut moved = 0;
+        let mut occ = vec![0; s];
+        let mut ahead = vec![0; s];
+        let mut arrive = vec![0; s];
+        for y in 0..self.height {
+            let east = &mut self.east[(y * s)..][..s];
+            let south = &self.south[(y * s)..][..s];
+            for i in 0..s {
+                occ[i] = east[i] | south[i]
+            }
+            rotate_from_right(occ.as_slice(), ahead.as_mut_slice(), self.width);
+            for i in 0..s {
+                // reuse occ for the cucumbers which can move
+                occ[i] = east[i] & !ahead[i];
+                moved += occ[i].count_ones() as usize;
+            }
+            rotate_from_left(occ.as_slice(), arrive.as_mut_slice(), self.width);
+            for i in 0..s {
+                east[i] = (east[i] & !occ[i]) | arrive[i]
+            }
+        }
+        moved
+    }
+
+    fn tick_south(&mut self) -> usize {
+        let s = self.stride;
+        let mut moved = 0;
+        let can = (0..self.height)
+            .flat_map(|y| {
+                let below = ((y + 1) % self.height) * s;
+                (0..s).map(move |i| (y * s + i, below + i))
+            })
+            .map(|(here, below)| self.south[here] & !(self.east[below] | self.south[below]))
+            .collect::<Vec<_>>();
+        for y in 0..self.height {
+            let above = ((y + self.height - 1) % self.height) * s;
+            for i in 0..s {
+                let here = y * s + i;
+                self.south[here] = (self.south[here] & !can[here]) | can[above + i];
+                moved += can[here].count_ones() as usize;
+            }
+        }
+        moved
+    }
+
+    // number of east and south movers which moved
+    pub(crate) fn tick(&mut self) -> (usize, usize) {
+        let r = self.tick_east();
+        let d = self.tick_south();
+        (r, d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_line;
+
+    fn grid(rows: &[&str]) -> Grid {
+        let (width, height) = (rows[0].len(), rows.len());
+        Grid {
+            data: rows.iter().flat_map(|r| parse_line(r)).collect(),
+            back: vec![Space::Empty; width * height],
+            width,
+            height
+        }
+    }
+
+    // a fixed pseudo-random herd, about a third of each kind
+    fn random_grid(width: usize, height: usize, seed: u64) -> Grid {
+        let mut state = seed;
+        let rows = (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ['.', '>', 'v'][(state >> 33) as usize % 3]
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        grid(rows.iter().map(|r| r.as_str()).collect::<Vec<_>>().as_slice())
+    }
+
+    // both kinds of grid step by step, comparing the herds and the number which moved
+    fn assert_same_ticks(mut g: Grid, steps: usize) {
+        let mut b = BitGrid::from_grid(&g);
+        for step in 1..=steps {
+            let moved = g.tick();
+            assert_eq!(b.tick(), moved, "moves differ at step {}, width {}", step, g.width);
+            assert_eq!(b.to_grid().to_string(), g.to_string(), "herds differ at step {}, width {}", step, g.width);
+        }
+    }
+
+    #[test]
+    fn matches_grid_on_word_sized_and_ragged_widths() {
+        for (seed, width) in [1, 7, 63, 64, 65, 100, 128, 139].into_iter().enumerate() {
+            assert_same_ticks(random_grid(width, 9, seed as u64), 20);
+        }
+    }
+
+    #[test]
+    fn wraps_at_both_edges() {
+        // movers on the last column and bottom row have to come back in on the first
+        for width in [5, 64, 65, 129] {
+            let mut rows = vec![".".repeat(width); 4];
+            rows[0].replace_range((width - 1).., ">");
+            rows[1].replace_range((width - 2).., ">>");
+            rows[3].replace_range(..1, "v");
+            rows[3].replace_range((width - 1).., "v");
+            let rows = rows.iter().map(|r| r.as_str()).collect::<Vec<_>>();
+            let g = grid(rows.as_slice());
+            let mut b = BitGrid::from_grid(&g);
+            b.tick();
+            // the east mover wraps to the first column and the south mover below the last
+            // column wraps up into the space it left
+            let top = format!(">{}v", ".".repeat(width - 2));
+            assert_eq!(b.to_grid().to_string().lines().next(), Some(top.as_str()));
+            assert_same_ticks(g, 2 * width);
+        }
+    }
+}
@@ -2,17 +2,22 @@
 #![feature(int_abs_diff)]
 #![feature(map_first_last)]
 
+mod bitgrid;
+
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::collections::hash_map::Entry;
 use std::fmt::{Arguments, Display, Formatter, Write};
+use std::io::Write as IoWrite;
 use std::fs::{File, read};
 use std::hash::Hash;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, stdout};
 use std::iter::from_fn;
 use std::mem::swap;
 use std::ops::Rem;
 use std::process::exit;
-use std::thread::Builder;
+use std::thread::{Builder, sleep};
+use std::time::{Duration, Instant};
+use crate::bitgrid::BitGrid;
 
 const FILENAME: &'static str = "i1.txt";
 
@@ -68,6 +73,7 @@ fn read_data() -> Grid {
         data: lines.into_iter()
             .flat_map(|l| parse_line(l.as_str()).collect::<Vec<_>>().into_iter())
             .collect(),
+        back: vec![Space::Empty; width * height],
         width,
         height
     }
@@ -75,6 +81,8 @@ fn read_data() -> Grid {
 
 struct Grid {
     data: Vec<Space>,
+    // scratch buffer for the next half-step, swapped with data afterwards
+    back: Vec<Space>,
     width: usize,
     height: usize
 }
@@ -114,42 +122,129 @@ impl Grid {
         self.data[y * self.width + x] = s;
     }
 
-    fn tick_space(&mut self, s: Space) -> bool {
-        let mut did_move = false;
-        let mut new_board = Grid {
-            data: vec![Space::Empty; self.data.len()],
-            width: self.width,
-            height: self.height
-        };
+    fn tick_space(&mut self, s: Space) -> usize {
+        let mut moved = 0;
+        self.back.copy_from_slice(self.data.as_slice());
         let diff = s.get_diff();
         for y in 0..self.height {
             for x in 0..self.width {
                 if (self.data[y * self.width + x] == s) && !self.is_occupied(x + diff.0, y + diff.1) {
-                    new_board.set(x + diff.0, y + diff.1, s);
-                    did_move = true;
-                } else if self.data[y * self.width + x] != Space::Empty {
-                    new_board.set(x, y, self.data[y * self.width + x]);
+                    let (nx, ny) = self.correct_coords(x + diff.0, y + diff.1);
+                    self.back[y * self.width + x] = Space::Empty;
+                    self.back[ny * self.width + nx] = s;
+                    moved += 1;
                 }
             }
         }
-        swap(&mut new_board, self);
-        did_move
+        swap(&mut self.data, &mut self.back);
+        moved
     }
 
-    fn tick(&mut self) -> bool {
+    // repeats the grid k times in both directions
+    fn tile(&self, k: usize) -> Self {
+        let width = self.width * k;
+        let height = self.height * k;
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(self.data[(y % self.height) * self.width + x % self.width])
+            }
+        }
+        Grid {
+            data,
+            back: vec![Space::Empty; width * height],
+            width,
+            height
+        }
+    }
+
+    // number of east and south movers which moved
+    fn tick(&mut self) -> (usize, usize) {
         let r = self.tick_space(Space::Right);
         let d = self.tick_space(Space::Down);
-        r || d
+        (r, d)
+    }
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
+}
+
+fn run_until_stable(mut tick: impl FnMut() -> (usize, usize)) -> usize {
+    for i in 1.. {
+        let (east, south) = tick();
+        if east + south == 0 {
+            return i
+        }
     }
+    unreachable!()
 }
 
 fn main() {
     let mut data = read_data();
+    if let Some(k) = arg_value("--tile") {
+        data = data.tile(k.parse().unwrap());
+    }
 
+    if std::env::args().any(|a| a == "--verify") {
+        // some herds never settle, so allow stopping early
+        let limit = arg_value("--steps").map_or(usize::MAX, |v| v.parse().unwrap());
+        let mut bits = BitGrid::from_grid(&data);
+        for i in 1..=limit {
+            let a = data.tick();
+            let b = bits.tick();
+            assert_eq!(a, b, "move counts differ at step {}", i);
+            assert!(bits.to_grid().data == data.data, "grids differ at step {}", i);
+            if a.0 + a.1 == 0 || i == limit {
+                println!("identical for {} steps", i);
+                return
+            }
+        }
+    }
+
+    if std::env::args().any(|a| a == "--bench") {
+        let mut bits = BitGrid::from_grid(&data);
+        let start = Instant::now();
+        let n = run_until_stable(|| data.tick());
+        println!("{}x{} cells: {} steps in {:?}", data.width, data.height, n, start.elapsed());
+        let start = Instant::now();
+        let n = run_until_stable(|| bits.tick());
+        println!("{}x{} bitwise: {} steps in {:?}", data.width, data.height, n, start.elapsed());
+        return
+    }
+
+    if std::env::args().any(|a| a == "--bitwise") {
+        let mut bits = BitGrid::from_grid(&data);
+        println!("1> {}", run_until_stable(|| bits.tick()));
+        return
+    }
+
+    let stats = std::env::args().any(|a| a == "--stats");
+    let frames = std::env::args().any(|a| a == "--frames");
+    let animate = std::env::args().any(|a| a == "--animate");
+    let delay = Duration::from_millis(arg_value("--delay").map_or(50, |v| v.parse().unwrap()));
+
+    let mut total = (0, 0);
     for i in 1.. {
-        //println!("i: {}", i);
-        //println!("{}", &data);
-        if !data.tick() {
+        let (east, south) = data.tick();
+        total = (total.0 + east, total.1 + south);
+        if stats {
+            println!("{:>4}: {} east, {} south", i, east, south);
+        }
+        if frames {
+            println!("After {} steps:\n{}\n", i, &data);
+        }
+        if animate {
+            print!("\x1b[H\x1b[2J{}\nstep {}: {} east, {} south\n", &data, i, east, south);
+            stdout().flush().unwrap();
+            sleep(delay);
+        }
+        if east + south == 0 {
+            if stats {
+                println!("total: {} east, {} south", total.0, total.1);
+            }
             println!("1> {}", i);
             break;
         }
@@ -0,0 +1,88 @@
+use crate::solver::{self, Layout, Mapping};
+
+pub(crate) fn letters(mask: u32) -> String {
+    (0..32)
+        .filter(|b| mask & (1 << b) != 0)
+        .map(|b| (b'a' + b as u8) as char)
+        .collect()
+}
+
+pub(crate) fn describe_mapping(m: &Mapping) -> String {
+    m.iter()
+        .enumerate()
+        .map(|(w, &s)| format!("{}->{}", letters(1 << w), letters(1 << s)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// glyphs side by side, three text rows high; only the seven standard segments are drawn
+pub(crate) fn render(segs: &[u32]) -> String {
+    let on = |m: u32, s: u32, c: char| if m & (1 << s) != 0 { c } else { ' ' };
+    let mut rows = [String::new(), String::new(), String::new()];
+    for &m in segs {
+        rows[0].extend([' ', on(m, 0, '_'), ' ', ' ']);
+        rows[1].extend([on(m, 1, '|'), on(m, 3, '_'), on(m, 2, '|'), ' ']);
+        rows[2].extend([on(m, 4, '|'), on(m, 6, '_'), on(m, 5, '|'), ' ']);
+    }
+    rows.iter().map(|r| r.trim_end()).collect::<Vec<_>>().join("\n")
+}
+
+// why no wiring fits: patterns of a size no symbol has, and patterns without which a wiring exists
+fn explain_conflict(layout: &Layout, observed: &[u32]) -> Vec<String> {
+    let mut distinct = observed.to_vec();
+    distinct.sort();
+    distinct.dedup();
+    let mut ret = distinct
+        .iter()
+        .filter(|&&p| layout.symbols.iter().all(|&(_, m)| m.count_ones() != p.count_ones()))
+        .map(|&p| format!("{} has {} segments, which no symbol has", letters(p), p.count_ones()))
+        .collect::<Vec<_>>();
+    if !ret.is_empty() {
+        return ret
+    }
+    for &p in distinct.iter() {
+        let rest = distinct.iter().copied().filter(|&q| q != p).collect::<Vec<_>>();
+        if !solver::solve(layout, rest.as_slice(), 1).is_empty() {
+            ret.push(format!("{} is inconsistent with the other patterns", letters(p)))
+        }
+    }
+    if ret.is_empty() {
+        ret.push("no single pattern explains the conflict".to_string())
+    }
+    ret
+}
+
+// why several wirings fit: symbols which were never shown, and outputs which decode differently
+fn explain_ambiguity(layout: &Layout, found: &[Mapping], observed: &[u32], output: &[u32]) -> Vec<String> {
+    let shown = observed
+        .iter()
+        .filter_map(|&p| layout.lookup(solver::apply(&found[0], p)))
+        .collect::<Vec<_>>();
+    let missing = (0..layout.symbols.len())
+        .filter(|i| !shown.contains(i))
+        .map(|i| layout.symbols[i].0.to_string())
+        .collect::<Vec<_>>();
+    let mut ret = Vec::new();
+    if !missing.is_empty() {
+        ret.push(format!("never shown: {}", missing.join(", ")))
+    }
+    for (i, &d) in output.iter().enumerate() {
+        let readings = found
+            .iter()
+            .map(|m| layout.symbols[layout.lookup(solver::apply(m, d)).unwrap()].0)
+            .collect::<String>();
+        if readings.chars().any(|c| Some(c) != readings.chars().next()) {
+            ret.push(format!("output digit {} ({}) could be any of {}", i + 1, letters(d), readings))
+        }
+    }
+    ret
+}
+
+// the problems with a line which doesn't decode to a single reading
+pub(crate) fn problems(layout: &Layout, found: &[Mapping], observed: &[u32], output: &[u32]) -> Vec<String> {
+    match found.len() {
+        0 => explain_conflict(layout, observed),
+        1 => Vec::new(),
+        _ => explain_ambiguity(layout, found, observed, output)
+    }
+}
@@ -2,6 +2,9 @@
 extern crate lazy_static;
 extern crate regex;
 
+mod diagnose;
+mod solver;
+
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::iter::{from_fn};
@@ -9,13 +12,7 @@ use std::ops::{BitAnd, BitOr, BitOrAssign, BitXor};
 
 use regex::Regex;
 
-const A: LetterSet = LetterSet::new(1);
-const B: LetterSet = LetterSet::new(2);
-const C: LetterSet = LetterSet::new(4);
-const D: LetterSet = LetterSet::new(8);
-const E: LetterSet = LetterSet::new(16);
-const F: LetterSet = LetterSet::new(32);
-const G: LetterSet = LetterSet::new(64);
+use solver::Layout;
 
 const FILENAME: &'static str = "i1.txt";
 lazy_static! {
@@ -88,29 +85,44 @@ fn read_lines() -> impl Iterator<Item = String> {
     })
 }
 
-fn read_data() -> impl Iterator<Item = Vec<LetterSet>> {
+struct Entry {
+    patterns: Vec<LetterSet>,
+    output: Vec<LetterSet>
+}
+
+impl Entry {
+    // everything seen on the line, which all has to decode
+    fn observed(&self) -> Vec<u32> {
+        self.patterns.iter().chain(self.output.iter()).map(|d| d.inner).collect()
+    }
+}
+
+fn read_data() -> impl Iterator<Item = Entry> {
     read_lines().map(|l| {
-        LINE_RE.captures_iter(l.as_str())
-            .map(|c| c.get(1).unwrap().as_str())
-            .filter_map(|e| {
-                if e == "|" {
-                    None
-                } else {
-                    Some(e.chars().fold(LetterSet::new(0), |acc, c| {
-                        match c {
-                            'a' => acc | A,
-                            'b' => acc | B,
-                            'c' => acc | C,
-                            'd' => acc | D,
-                            'e' => acc | E,
-                            'f' => acc | F,
-                            'g' => acc | G,
-                            _ => panic!()
-                        }
-                    }))
+        let mut patterns = Vec::new();
+        let mut output = Vec::new();
+        let mut seen_bar = false;
+        for e in LINE_RE.captures_iter(l.as_str()).map(|c| c.get(1).unwrap().as_str()) {
+            if e == "|" {
+                seen_bar = true;
+                continue
+            }
+            let d = e.chars().fold(LetterSet::new(0), |acc, c| {
+                match c {
+                    'a'..='z' => acc | LetterSet::new(1 << (c as u32 - 'a' as u32)),
+                    _ => panic!()
                 }
-            })
-            .collect()
+            });
+            if seen_bar {
+                output.push(d)
+            } else {
+                patterns.push(d)
+            }
+        }
+        Entry {
+            patterns,
+            output
+        }
     })
 }
 
@@ -144,41 +156,97 @@ fn get_true_digits(digits: &[LetterSet]) -> [LetterSet; 10] {
     ret
 }
 
-fn p1(data: &Vec<Vec<LetterSet>>) {
+// lengths which only one symbol of the layout has
+fn unique_lengths(layout: &Layout) -> Vec<u32> {
+    layout.symbols
+        .iter()
+        .map(|&(_, m)| m.count_ones())
+        .filter(|&n| layout.symbols.iter().filter(|&&(_, m)| m.count_ones() == n).count() == 1)
+        .collect()
+}
+
+fn p1(data: &Vec<Entry>, layout: &Layout) {
+    let unique = unique_lengths(layout);
     let r = data.iter()
-        .map(|v| v.iter().skip(10))
-        .flatten().filter_map(|v| {
-        match v.letter_cnt() {
-            2 => Some(()),
-            4 => Some(()),
-            3 => Some(()),
-            7 => Some(()),
-            _ => None
-        }
-    }).count();
+        .flat_map(|e| e.output.iter())
+        .filter(|v| unique.contains(&v.letter_cnt()))
+        .count();
     println!("1> {}", r);
 }
 
-fn p2(data: &Vec<Vec<LetterSet>>) {
-    let sum = data.iter().map(|entry| {
-        let true_dig = get_true_digits(&entry.as_slice()[0..10]);
-        entry.as_slice()[10..14].iter().map(|d| {
-            true_dig.iter()
-                .enumerate()
-                .find_map(|v| {
-                    if *d == *v.1 {
-                        Some(v.0)
-                    } else {
-                        None
-                    }
-                }).unwrap()
-        }).fold(0, |acc, v| acc * 10 + (v as u64))
+// the old hand derived identities, which only hold for the standard digits
+fn p2_xor(data: &Vec<Entry>) {
+    let sum = data.iter().enumerate().filter_map(|(line, entry)| {
+        let true_dig = get_true_digits(entry.patterns.as_slice());
+        let digits = entry.output.iter().map(|d| {
+            true_dig.iter().position(|v| *v == *d).ok_or(*d)
+        }).collect::<Result<Vec<_>, _>>();
+        match digits {
+            Ok(digits) => Some(digits.iter().fold(0, |acc, &v| acc * 10 + (v as u64))),
+            Err(d) => {
+                println!("line {}: {} matches no derived digit", line + 1, diagnose::letters(d.inner));
+                None
+            }
+        }
     }).sum::<u64>();
     println!("2> {}", sum);
 }
 
+fn p2(data: &Vec<Entry>, layout: &Layout, verbose: bool) {
+    let base = layout.symbols.len() as u64;
+    let mut sum = 0;
+    let mut failed = 0;
+    for (line, entry) in data.iter().enumerate() {
+        let observed = entry.observed();
+        let output = entry.output.iter().map(|d| d.inner).collect::<Vec<_>>();
+        let found = solver::solve(layout, observed.as_slice(), 2);
+        if let [m] = found.as_slice() {
+            let segs = output.iter().map(|&d| solver::apply(m, d)).collect::<Vec<_>>();
+            let idx = segs.iter().map(|&s| layout.lookup(s).unwrap()).collect::<Vec<_>>();
+            if verbose {
+                println!("line {}: {}", line + 1, diagnose::describe_mapping(m));
+                println!("{}", diagnose::render(segs.as_slice()));
+                println!("  = {}", idx.iter().map(|&i| layout.symbols[i].0).collect::<String>());
+            }
+            sum += idx.iter().fold(0, |acc, &v| acc * base + v as u64);
+            continue
+        }
+        failed += 1;
+        if found.is_empty() {
+            println!("line {}: no wiring matches the layout", line + 1)
+        } else {
+            println!("line {}: ambiguous wiring, observations allow several", line + 1)
+        }
+        for p in diagnose::problems(layout, found.as_slice(), observed.as_slice(), output.as_slice()) {
+            println!("  {}", p)
+        }
+    }
+    if failed > 0 {
+        println!("{} lines skipped", failed)
+    }
+    println!("2> {}", sum);
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
+}
+
 fn main() {
+    let layout = match arg_value("--layout").as_deref() {
+        None | Some("digits") => Layout::seven_segment(),
+        Some("hex") => Layout::hex(),
+        Some(path) => {
+            let s = std::fs::read_to_string(path).unwrap();
+            Layout::parse(s.as_str()).expect("bad layout file")
+        }
+    };
     let data = read_data().collect::<Vec<_>>();
-    p1(&data);
-    p2(&data);
+    p1(&data, &layout);
+    if std::env::args().any(|a| a == "--xor") {
+        p2_xor(&data)
+    } else {
+        p2(&data, &layout, std::env::args().any(|a| a == "--diagnose"))
+    }
 }
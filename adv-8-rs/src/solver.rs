@@ -0,0 +1,150 @@
+// a display, given as the lit segments of each symbol, with segment i as bit i,
+// segments named as in the puzzle: a top, b/c upper left/right, d middle,
+// e/f lower left/right, g bottom
+#[derive(Clone, Debug)]
+pub(crate) struct Layout {
+    pub(crate) symbols: Vec<(char, u32)>,
+    pub(crate) segments: usize
+}
+
+fn letters_to_mask(s: &str) -> Option<u32> {
+    s.chars().try_fold(0, |acc, c| {
+        if c.is_ascii_lowercase() {
+            Some(acc | (1 << (c as u32 - 'a' as u32)))
+        } else {
+            None
+        }
+    })
+}
+
+impl Layout {
+    // lines of "<symbol> <segments>", e.g. "7 acf"
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let symbols = s
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| {
+                let (sym, segs) = l.trim().split_once(' ')?;
+                let mut sym = sym.chars();
+                let c = sym.next()?;
+                sym.next().is_none().then_some(())?;
+                Some((c, letters_to_mask(segs.trim())?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let segments = symbols
+            .iter()
+            .map(|&(_, m)| 32 - m.leading_zeros() as usize)
+            .max()?;
+        Some(Layout {
+            symbols,
+            segments
+        })
+    }
+
+    pub(crate) fn seven_segment() -> Self {
+        Layout::parse("0 abcefg\n1 cf\n2 acdeg\n3 acdfg\n4 bcdf\n5 abdfg\n6 abdefg\n7 acf\n8 abcdefg\n9 abcdfg").unwrap()
+    }
+
+    pub(crate) fn hex() -> Self {
+        let mut ret = Layout::seven_segment();
+        ret.symbols.extend(Layout::parse("A abcdef\nb bdefg\nC abeg\nd cdefg\nE abdeg\nF abde").unwrap().symbols);
+        ret
+    }
+
+    // index of the symbol with exactly these segments lit
+    pub(crate) fn lookup(&self, segs: u32) -> Option<usize> {
+        self.symbols.iter().position(|&(_, m)| m == segs)
+    }
+}
+
+// wire i drives segment mapping[i]
+pub(crate) type Mapping = Vec<usize>;
+
+pub(crate) fn apply(mapping: &Mapping, wires: u32) -> u32 {
+    mapping
+        .iter()
+        .enumerate()
+        .filter(|&(w, _)| wires & (1 << w) != 0)
+        .fold(0, |acc, (_, &s)| acc | (1 << s))
+}
+
+struct Search<'a> {
+    layout: &'a Layout,
+    observed: &'a [u32],
+    // segments each wire could still drive
+    candidates: Vec<u32>,
+    assigned: Vec<Option<usize>>,
+    used: u32,
+    found: Vec<Mapping>,
+    limit: usize
+}
+
+impl<'a> Search<'a> {
+    // every observed pattern must still be able to become some symbol
+    fn consistent(&self) -> bool {
+        self.observed.iter().all(|&p| {
+            self.layout.symbols.iter().any(|&(_, m)| {
+                m.count_ones() == p.count_ones() && self.assigned
+                    .iter()
+                    .enumerate()
+                    .all(|(w, s)| match s {
+                        Some(s) => (p & (1 << w) != 0) == (m & (1 << s) != 0),
+                        None => true
+                    })
+            })
+        })
+    }
+
+    fn run(&mut self) {
+        if self.found.len() >= self.limit || !self.consistent() {
+            return
+        }
+        let next = (0..self.assigned.len())
+            .filter(|&w| self.assigned[w].is_none())
+            .min_by_key(|&w| (self.candidates[w] & !self.used).count_ones());
+        let w = match next {
+            Some(w) => w,
+            None => {
+                self.found.push(self.assigned.iter().map(|s| s.unwrap()).collect());
+                return
+            }
+        };
+        let options = self.candidates[w] & !self.used;
+        for s in 0..self.layout.segments {
+            if options & (1 << s) != 0 {
+                self.assigned[w] = Some(s);
+                self.used |= 1 << s;
+                self.run();
+                self.used &= !(1 << s);
+                self.assigned[w] = None;
+            }
+        }
+    }
+}
+
+// every wiring which turns all observed patterns into symbols, stopping after limit
+pub(crate) fn solve(layout: &Layout, observed: &[u32], limit: usize) -> Vec<Mapping> {
+    let all = (1u32 << layout.segments) - 1;
+    let mut candidates = vec![all; layout.segments];
+    // propagate the symbols each pattern could be, judging by size alone
+    for &p in observed {
+        let (on, off) = layout.symbols
+            .iter()
+            .filter(|&&(_, m)| m.count_ones() == p.count_ones())
+            .fold((0, 0), |(on, off), &(_, m)| (on | m, off | (all & !m)));
+        for (w, c) in candidates.iter_mut().enumerate() {
+            *c &= if p & (1 << w) != 0 { on } else { off }
+        }
+    }
+    let mut search = Search {
+        layout,
+        observed,
+        candidates,
+        assigned: vec![None; layout.segments],
+        used: 0,
+        found: Vec::new(),
+        limit
+    };
+    search.run();
+    search.found
+}
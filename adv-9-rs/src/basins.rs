@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use crate::get_adjacent;
+
+pub(crate) struct Basin {
+    // first cell of the lowest area, which may be a flat plateau
+    pub(crate) low: (usize, usize),
+    pub(crate) height: u8,
+    pub(crate) size: usize
+}
+
+pub(crate) struct Basins {
+    // basin of every cell, None for the 9 walls
+    pub(crate) label: Vec<Vec<Option<usize>>>,
+    pub(crate) basins: Vec<Basin>,
+    // cells which drain into more than one basin, with every basin they reach,
+    // labelled with the first of them
+    pub(crate) shared: Vec<((usize, usize), Vec<usize>)>
+}
+
+// connected cells of the same height as (y, x), marking them as grouped
+pub(crate) fn flat_area(data: &Vec<Vec<u8>>, grouped: &mut Vec<Vec<bool>>, y: usize, x: usize) -> Vec<(usize, usize)> {
+    let (height, width) = (data.len(), data[0].len());
+    let h = data[y][x];
+    let mut ret = vec![(y, x)];
+    let mut queue = VecDeque::from([(y, x)]);
+    grouped[y][x] = true;
+    while let Some((y, x)) = queue.pop_front() {
+        for c in get_adjacent(y, x, height, width) {
+            if data[c.0][c.1] == h && !grouped[c.0][c.1] {
+                grouped[c.0][c.1] = true;
+                ret.push(c);
+                queue.push_back(c);
+            }
+        }
+    }
+    ret
+}
+
+// watershed labelling, filling in cells from the lowest up so that each cell
+// joins the basins of the lower cells it can flow into
+pub(crate) fn label(data: &Vec<Vec<u8>>) -> Basins {
+    let (height, width) = (data.len(), data[0].len());
+    // every basin each labelled cell drains into, sorted
+    let mut reach: Vec<Vec<Option<Vec<usize>>>> = vec![vec![None; width]; height];
+    let mut grouped = vec![vec![false; width]; height];
+    let mut basins = Vec::new();
+    for h in 0..9 {
+        for y in 0..height {
+            for x in 0..width {
+                if data[y][x] != h || grouped[y][x] {
+                    continue
+                }
+                let area = flat_area(data, &mut grouped, y, x);
+                let mut to = area
+                    .iter()
+                    .flat_map(|&(y, x)| get_adjacent(y, x, height, width))
+                    .filter(|c| data[c.0][c.1] < h)
+                    .flat_map(|c| reach[c.0][c.1].clone().unwrap())
+                    .collect::<Vec<_>>();
+                to.sort();
+                to.dedup();
+                if to.is_empty() {
+                    to.push(basins.len());
+                    basins.push(Basin {
+                        low: (y, x),
+                        height: h,
+                        size: 0
+                    });
+                }
+                for &(y, x) in area.iter() {
+                    reach[y][x] = Some(to.clone())
+                }
+            }
+        }
+    }
+    let mut label = vec![vec![None; width]; height];
+    let mut shared = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(to) = reach[y][x].take() {
+                basins[to[0]].size += 1;
+                label[y][x] = Some(to[0]);
+                if to.len() > 1 {
+                    shared.push(((y, x), to))
+                }
+            }
+        }
+    }
+    Basins {
+        label,
+        basins,
+        shared
+    }
+}
+
+// xterm 256 colour of a basin, spreading neighbouring ids over the 6x6x6 colour cube
+pub(crate) fn colour(id: usize) -> u8 {
+    (16 + (id * 47) % 216) as u8
+}
+
+// one character per cell, basins cycling through letters, walls as # and shared cells as *;
+// with ansi each basin gets a background colour instead and cells show their height
+pub(crate) fn render(data: &Vec<Vec<u8>>, b: &Basins, ansi: bool) -> String {
+    const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let mut is_shared = vec![vec![false; data[0].len()]; data.len()];
+    for &((y, x), _) in b.shared.iter() {
+        is_shared[y][x] = true
+    }
+    let mut ret = String::new();
+    for (y, row) in data.iter().enumerate() {
+        for (x, &h) in row.iter().enumerate() {
+            let is_shared = is_shared[y][x];
+            match (b.label[y][x], ansi) {
+                (None, false) => ret.push('#'),
+                (None, true) => ret.push_str("\x1b[90m9"),
+                (Some(_), false) if is_shared => ret.push('*'),
+                (Some(id), false) => ret.push(LETTERS[id % LETTERS.len()] as char),
+                (Some(_), true) if is_shared => {
+                    ret.push_str(format!("\x1b[1;97;41m{}", h).as_str())
+                }
+                (Some(id), true) => {
+                    ret.push_str(format!("\x1b[30;48;5;{}m{}", colour(id), h).as_str())
+                }
+            }
+            if ansi {
+                ret.push_str("\x1b[0m")
+            }
+        }
+        ret.push('\n');
+    }
+    ret
+}
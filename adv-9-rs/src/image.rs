@@ -0,0 +1,41 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use crate::basins::{self, Basins};
+
+// heights as a binary greyscale image, 0 black and 9 white
+pub(crate) fn write_pgm(path: &str, data: &Vec<Vec<u8>>) -> Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    write!(w, "P5\n{} {}\n255\n", data[0].len(), data.len())?;
+    for row in data {
+        w.write_all(row.iter().map(|&h| (h as u16 * 255 / 9) as u8).collect::<Vec<_>>().as_slice())?;
+    }
+    w.flush()
+}
+
+// rgb of an xterm colour cube entry
+fn cube_rgb(colour: u8) -> [u8; 3] {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let n = (colour - 16) as usize;
+    [LEVELS[n / 36], LEVELS[(n / 6) % 6], LEVELS[n % 6]]
+}
+
+// basins in the same colours as the terminal map, walls black and shared cells red
+pub(crate) fn write_ppm(path: &str, b: &Basins) -> Result<()> {
+    let mut is_shared = vec![vec![false; b.label[0].len()]; b.label.len()];
+    for &((y, x), _) in b.shared.iter() {
+        is_shared[y][x] = true
+    }
+    let mut w = BufWriter::new(File::create(path)?);
+    write!(w, "P6\n{} {}\n255\n", b.label[0].len(), b.label.len())?;
+    for (y, row) in b.label.iter().enumerate() {
+        for (x, l) in row.iter().enumerate() {
+            let rgb = match l {
+                None => [0, 0, 0],
+                Some(_) if is_shared[y][x] => [255, 0, 0],
+                Some(id) => cube_rgb(basins::colour(*id))
+            };
+            w.write_all(&rgb)?;
+        }
+    }
+    w.flush()
+}
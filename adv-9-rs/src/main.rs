@@ -1,127 +1,41 @@
-#![feature(maybe_uninit_uninit_array)]
-#![feature(maybe_uninit_array_assume_init)]
 #![feature(mixed_integer_ops)]
 #![feature(cell_update)]
 #[macro_use]
 extern crate lazy_static;
 
+mod basins;
+mod image;
+// shared between days, which each only use part of it
+#[allow(dead_code)]
+#[path = "../../common/lexer.rs"]
+mod lexer;
+mod stats;
+
 use std::borrow::Borrow;
 use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::iter::{from_fn, Peekable};
-use std::mem::MaybeUninit;
 use std::ops::Deref;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::lexer::Lexer;
 
 //const FILENAME: &'static str = "bb-9-4096-4.in.txt";
 const FILENAME: &'static str = "i1.txt";
 
-fn chunk_count<V, const N: usize>(mut it: impl Iterator<Item=V>) -> impl Iterator<Item=[V; N]> {
-    from_fn(move || {
-        let mut ret = MaybeUninit::uninit_array();
-        for pos in 0..N {
-            if pos == 0 {
-                ret[pos] = MaybeUninit::new(it.next()?);
-            } else {
-                ret[pos] = MaybeUninit::new(it.next().unwrap());
-            }
-        }
-        unsafe {
-            Some(MaybeUninit::array_assume_init(ret))
-        }
-    })
-}
-
-fn take_number(it: &mut Peekable<impl Iterator<Item=char>>) -> Option<u32> {
-    let mut acc = 0;
-    let has_n = false;
-    while let Some(c) = it.next_if(|v| v.is_ascii_digit()) {
-        acc *= 10;
-        acc += (c as u32) - ('0' as u32);
-    }
-    if has_n {
-        Some(acc)
-    } else {
-        None
-    }
-}
-
-fn take_white_numbers(it: &mut Peekable<impl Iterator<Item=char>>) -> Vec<u32> {
-    from_fn(|| {
-        take_ignore_white(it, take_number)
-    }).collect()
-}
-
-fn map_nonwhite_chunks(mut it: impl Iterator<Item=char>) -> impl Iterator<Item=String> {
-    let mut acc = None;
-    let mut cont = true;
-    from_fn(move || {
-        if !cont {
-            return None
-        }
-        loop {
-            match it.next() {
-                Some(c) if c.is_ascii_whitespace() => {
-                    if acc.is_some() {
-                        return acc.take();
-                    }
-                },
-                Some(c) => {
-                    acc.get_or_insert(String::new()).push(c);
-                }
-                None => {
-                    cont = false;
-                    return acc.take()
-                }
+fn read_data() -> Vec<Vec<u8>> {
+    let src = std::fs::read_to_string(FILENAME).unwrap();
+    let mut lex = Lexer::new(src.as_str());
+    let mut ret: Vec<Vec<u8>> = Vec::new();
+    while !lex.is_done() {
+        let (row, span) = lex.digits().unwrap_or_else(|e| panic!("{}: {}", FILENAME, e));
+        if let Some(first) = ret.first() {
+            if first.len() != row.len() {
+                panic!("{}: line {}: row of {} heights, expected {}", FILENAME, span.line, row.len(), first.len())
             }
         }
-    })
-}
-
-fn take_ignore_white<I: Iterator<Item=char>, V>(it: &mut Peekable<I>, tk_fn: impl FnOnce(&mut Peekable<I>) -> V) -> V {
-    take_any_white(it);
-    (tk_fn)(it)
-}
-
-fn take_any_white(it: &mut Peekable<impl Iterator<Item=char>>) {
-    while let Some(_) = it.next_if(|v| v.is_ascii_whitespace()) {
-    }
-}
-
-fn expect_char(it: &mut Peekable<impl Iterator<Item=char>>, c: char) -> Result<(), ()> {
-    it.next_if_eq(&c).map(|v| ()).ok_or(())
-}
-
-fn take_nonwhite(it: &mut Peekable<impl Iterator<Item=char>>) -> Option<String> {
-    let mut s = String::new();
-    while let Some(c) = it.next_if(|v| v.is_ascii_whitespace()) {
-        s.push(c)
+        ret.push(row)
     }
-    if s.len() != 0 {
-        Some(s)
-    } else {
-        None
-    }
-}
-
-fn read_lines() -> impl Iterator<Item = String> {
-    let file = File::open(FILENAME).unwrap();
-    let mut buf = BufReader::new(file);
-    from_fn(move || {
-        let mut s = String::new();
-        if buf.read_line(&mut s).unwrap() == 0 {
-            None
-        } else {
-            Some(s)
-        }
-    })
-}
-
-fn read_data() -> Vec<Vec<u8>> {
-    read_lines().map(|v| v.chars().filter(|c| c.is_ascii_digit()).map(|c| (c as u8) - ('0' as u8)).collect()).collect()
+    ret
 }
 
 fn get_dig_bound(data: &Vec<Vec<u8>>, y: isize, x: isize) -> u8 {
@@ -145,19 +59,10 @@ fn get_adjacent(y: usize, x: usize, y_max: usize, x_max: usize) -> impl Iterator
     })
 }
 
-fn find_drains_to(data: &Vec<Vec<u8>>, y: usize, x: usize, y_max: usize, x_max: usize) -> HashSet<(usize, usize)> {
-    let mut ret = HashSet::new();
-    let mut queue = VecDeque::new();
-    queue.push_front((y, x));
-    while let Some(coord) = queue.pop_front() {
-        if ret.insert(coord) {
-            queue.extend(get_adjacent(coord.0, coord.1, y_max, x_max).filter(|c| {
-                let n = data[c.0][c.1];
-                (n >= data[coord.0][coord.1]) && (n != 9)
-            }));
-        }
-    }
-    ret
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
 }
 
 fn main() {
@@ -181,21 +86,34 @@ fn main() {
     println!("1> {}", res_1);
 
     // part 2
-    let mut max_1 = 0;
-    let mut max_2 = 0;
-    let mut max_3 = 0;
-    for low in low_points {
-        let n = find_drains_to(&data, low.0, low.1, height, width).len();
-        if n > max_1 {
-            max_3 = max_2;
-            max_2 = max_1;
-            max_1 = n;
-        } else if n > max_2 {
-            max_3 = max_2;
-            max_2 = n;
-        } else if n > max_3 {
-            max_3 = n;
+    let b = basins::label(&data);
+    let mut sizes = b.basins.iter().map(|b| b.size).collect::<Vec<_>>();
+    sizes.sort_by(|a, b| b.cmp(a));
+    println!("2> {}", sizes.iter().take(3).product::<usize>());
+
+    if std::env::args().any(|a| a == "--basins") {
+        for (id, basin) in b.basins.iter().enumerate() {
+            println!(
+                "basin {}: low point {},{} at height {}, size {}",
+                id, basin.low.0, basin.low.1, basin.height, basin.size
+            );
         }
+        for ((y, x), to) in b.shared.iter() {
+            let to = to.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+            println!("{},{} drains into basins {}", y, x, to.join(", "));
+        }
+        println!("{} basins, {} low points, {} shared cells", b.basins.len(), low_points.len(), b.shared.len());
+    }
+    if std::env::args().any(|a| a == "--stats") {
+        stats::report(&data, &b);
+    }
+    if let Some(path) = arg_value("--pgm") {
+        image::write_pgm(path.as_str(), &data).unwrap();
+    }
+    if let Some(path) = arg_value("--ppm") {
+        image::write_ppm(path.as_str(), &b).unwrap();
+    }
+    if std::env::args().any(|a| a == "--map") {
+        print!("{}", basins::render(&data, &b, !std::env::args().any(|a| a == "--text")));
     }
-    println!("2> {}", max_1 * max_2 * max_3);
 }
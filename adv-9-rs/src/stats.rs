@@ -0,0 +1,53 @@
+use crate::basins::{flat_area, Basins};
+use crate::get_adjacent;
+
+// cell edges of each basin which border another basin, a wall or the edge of the map
+fn perimeters(b: &Basins) -> Vec<usize> {
+    let (height, width) = (b.label.len(), b.label[0].len());
+    let mut ret = vec![0; b.basins.len()];
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(id) = b.label[y][x] {
+                let inside = get_adjacent(y, x, height, width)
+                    .filter(|c| b.label[c.0][c.1] == Some(id))
+                    .count();
+                ret[id] += 4 - inside
+            }
+        }
+    }
+    ret
+}
+
+// flat areas of two or more cells below 9
+fn plateaus(data: &Vec<Vec<u8>>) -> usize {
+    let mut grouped = vec![vec![false; data[0].len()]; data.len()];
+    let mut ret = 0;
+    for y in 0..data.len() {
+        for x in 0..data[0].len() {
+            if data[y][x] != 9 && !grouped[y][x] && flat_area(data, &mut grouped, y, x).len() > 1 {
+                ret += 1
+            }
+        }
+    }
+    ret
+}
+
+pub(crate) fn report(data: &Vec<Vec<u8>>, b: &Basins) {
+    let mut hist = [0usize; 10];
+    for &h in data.iter().flatten() {
+        hist[h as usize] += 1
+    }
+    let most = hist.iter().copied().max().unwrap_or(0).max(1);
+    println!("heights:");
+    for (h, &n) in hist.iter().enumerate() {
+        println!("  {} {:>7} {}", h, n, "#".repeat((n * 50 + most - 1) / most));
+    }
+    let perimeters = perimeters(b);
+    if let Some((id, &p)) = perimeters.iter().enumerate().max_by_key(|&(id, &p)| (b.basins[id].size, p)) {
+        println!("largest basin: {} with size {} and perimeter {}", id, b.basins[id].size, p);
+    }
+    if let Some((id, &p)) = perimeters.iter().enumerate().max_by_key(|&(_, &p)| p) {
+        println!("longest perimeter: basin {} with {}", id, p);
+    }
+    println!("plateaus: {}", plateaus(data));
+}
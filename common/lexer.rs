@@ -0,0 +1,385 @@
+// shared tokenizer for puzzle inputs, include with
+//     #[path = "../../common/lexer.rs"]
+//     mod lexer;
+
+use std::fmt::{Display, Formatter};
+use std::iter::{from_fn, once};
+
+// byte range of a token in the source, with the 1 based line and column it starts at
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) line: usize,
+    pub(crate) col: usize
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind<'a> {
+    Number(i64),
+    // letters, digits and underscores, starting with a letter or underscore
+    Ident(&'a str),
+    // any other single ascii character which isn't whitespace
+    Punct(char)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub(crate) kind: TokenKind<'a>,
+    pub(crate) span: Span
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LexErrorKind {
+    Unexpected(char),
+    Overflow,
+    Expected(&'static str),
+    ExpectedPunct(char),
+    EndOfInput
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LexError {
+    pub(crate) span: Span,
+    pub(crate) kind: LexErrorKind
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("line {}, col {}: ", self.span.line, self.span.col))?;
+        match &self.kind {
+            LexErrorKind::Unexpected(c) => f.write_fmt(format_args!("unexpected character {:?}", c)),
+            LexErrorKind::Overflow => f.write_str("number too large"),
+            LexErrorKind::Expected(what) => f.write_fmt(format_args!("expected {}", what)),
+            LexErrorKind::ExpectedPunct(c) => f.write_fmt(format_args!("expected {:?}", c)),
+            LexErrorKind::EndOfInput => f.write_str("unexpected end of input")
+        }
+    }
+}
+
+impl std::error::Error for LexError {}
+
+pub(crate) struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    col: usize,
+    // a sign straight after a number or identifier, with no whitespace between, is
+    // punctuation, as in 3-4
+    after_value: bool,
+    peeked: Option<Result<Token<'a>, LexError>>,
+    // after_value from before the peeked token was lexed
+    before_peek: bool
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        Lexer {
+            src,
+            pos: 0,
+            line: 1,
+            col: 1,
+            after_value: false,
+            peeked: None,
+            before_peek: false
+        }
+    }
+
+    fn peek_char(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek_char(0)?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn here(&self) -> Span {
+        Span {
+            start: self.pos,
+            end: self.pos,
+            line: self.line,
+            col: self.col
+        }
+    }
+
+    fn skip_white(&mut self) {
+        while self.peek_char(0).map_or(false, |c| c.is_whitespace()) {
+            self.bump();
+            self.after_value = false;
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek_char(0).map_or(false, &f) {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn lex(&mut self) -> Option<Result<Token<'a>, LexError>> {
+        self.skip_white();
+        let mut span = self.here();
+        let c = self.peek_char(0)?;
+        let signed = matches!(c, '-' | '+')
+            && !self.after_value
+            && self.peek_char(1).map_or(false, |d| d.is_ascii_digit());
+        let kind = if c.is_ascii_digit() || signed {
+            let negative = c == '-';
+            if signed {
+                self.bump();
+            }
+            let digits = self.take_while(|c| c.is_ascii_digit());
+            let n = digits.bytes().try_fold(0i64, |acc, d| {
+                let d = (d - b'0') as i64;
+                acc.checked_mul(10)?.checked_add(if negative { -d } else { d })
+            });
+            match n {
+                Some(n) => Ok(TokenKind::Number(n)),
+                None => Err(LexErrorKind::Overflow)
+            }
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            Ok(TokenKind::Ident(self.take_while(|c| c.is_ascii_alphanumeric() || c == '_')))
+        } else if c.is_ascii_punctuation() {
+            self.bump();
+            Ok(TokenKind::Punct(c))
+        } else {
+            self.bump();
+            Err(LexErrorKind::Unexpected(c))
+        };
+        span.end = self.pos;
+        self.after_value = matches!(kind, Ok(TokenKind::Number(_) | TokenKind::Ident(_)));
+        Some(match kind {
+            Ok(kind) => Ok(Token { kind, span }),
+            Err(kind) => Err(LexError { span, kind })
+        })
+    }
+
+    pub(crate) fn peek(&mut self) -> Option<&Result<Token<'a>, LexError>> {
+        if self.peeked.is_none() {
+            self.before_peek = self.after_value;
+            self.peeked = self.lex();
+        }
+        self.peeked.as_ref()
+    }
+
+    // forget the peeked token, so lexing starts again from where it began
+    fn unpeek(&mut self) {
+        if let Some(t) = self.peeked.take() {
+            let span = match t {
+                Ok(t) => t.span,
+                Err(e) => e.span
+            };
+            self.pos = span.start;
+            self.line = span.line;
+            self.col = span.col;
+            self.after_value = self.before_peek;
+        }
+    }
+
+    // a run of digits taken one at a time, for inputs such as height maps where every
+    // digit stands alone
+    pub(crate) fn digits(&mut self) -> Result<(Vec<u8>, Span), LexError> {
+        self.unpeek();
+        self.skip_white();
+        let mut span = self.here();
+        let run = self.take_while(|c| c.is_ascii_digit());
+        span.end = self.pos;
+        if run.is_empty() {
+            let kind = match self.peek_char(0) {
+                Some(_) => LexErrorKind::Expected("digits"),
+                None => LexErrorKind::EndOfInput
+            };
+            return Err(LexError { span, kind })
+        }
+        self.after_value = true;
+        Ok((run.bytes().map(|d| d - b'0').collect(), span))
+    }
+
+    pub(crate) fn is_done(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    // the next token if it matches, otherwise an error pointing at whatever is there instead
+    fn expect<V>(&mut self, what: LexErrorKind, f: impl FnOnce(TokenKind<'a>) -> Option<V>) -> Result<(V, Span), LexError> {
+        let span = match self.peek() {
+            None => return Err(LexError { span: self.here(), kind: LexErrorKind::EndOfInput }),
+            Some(Err(_)) => return Err(self.peeked.take().unwrap().unwrap_err()),
+            Some(Ok(t)) => t.span
+        };
+        let kind = self.peeked.as_ref().unwrap().as_ref().unwrap().kind;
+        match f(kind) {
+            Some(v) => {
+                self.peeked = None;
+                Ok((v, span))
+            }
+            None => Err(LexError { span, kind: what })
+        }
+    }
+
+    pub(crate) fn expect_number(&mut self) -> Result<(i64, Span), LexError> {
+        self.expect(LexErrorKind::Expected("a number"), |k| match k {
+            TokenKind::Number(n) => Some(n),
+            _ => None
+        })
+    }
+
+    pub(crate) fn expect_ident(&mut self) -> Result<(&'a str, Span), LexError> {
+        self.expect(LexErrorKind::Expected("an identifier"), |k| match k {
+            TokenKind::Ident(s) => Some(s),
+            _ => None
+        })
+    }
+
+    pub(crate) fn expect_punct(&mut self, c: char) -> Result<Span, LexError> {
+        self.expect(LexErrorKind::ExpectedPunct(c), |k| (k == TokenKind::Punct(c)).then_some(()))
+            .map(|(_, s)| s)
+    }
+
+    // numbers until the end of input, skipping over whitespace only
+    pub(crate) fn numbers(&mut self) -> Result<Vec<i64>, LexError> {
+        let mut ret = Vec::new();
+        while !self.is_done() {
+            ret.push(self.expect_number()?.0)
+        }
+        Ok(ret)
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(t) => Some(t),
+            None => self.lex()
+        }
+    }
+}
+
+// every number in the source, ignoring whatever separates them
+pub(crate) fn all_numbers(src: &str) -> Result<Vec<i64>, LexError> {
+    Lexer::new(src)
+        .filter_map(|t| match t {
+            Ok(Token { kind: TokenKind::Number(n), .. }) => Some(Ok(n)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e))
+        })
+        .collect()
+}
+
+// groups of N items, panicking if the items run out part way through a group
+pub(crate) fn chunks<V, const N: usize>(mut it: impl Iterator<Item=V>) -> impl Iterator<Item=[V; N]> {
+    assert!(N > 0);
+    from_fn(move || {
+        let first = it.next()?;
+        let group = once(first).chain(it.by_ref().take(N - 1)).collect::<Vec<_>>();
+        match <[V; N]>::try_from(group) {
+            Ok(group) => Some(group),
+            Err(group) => panic!("{} items left over, expected groups of {}", group.len(), N)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(src: &str) -> Vec<TokenKind<'_>> {
+        Lexer::new(src).map(|t| t.unwrap().kind).collect()
+    }
+
+    #[test]
+    fn signed_and_unsigned_numbers() {
+        assert_eq!(all_numbers("1 -2 +3 04"), Ok(vec![1, -2, 3, 4]));
+        assert_eq!(Lexer::new("5 -6\n-7").numbers(), Ok(vec![5, -6, -7]));
+        assert_eq!(all_numbers("x=-5..+10,y=-3..-1"), Ok(vec![-5, 10, -3, -1]));
+    }
+
+    #[test]
+    fn adjacent_sign_is_punctuation() {
+        use TokenKind::*;
+        assert_eq!(kinds("3-4"), vec![Number(3), Punct('-'), Number(4)]);
+        assert_eq!(kinds("3 -4"), vec![Number(3), Number(-4)]);
+        assert_eq!(kinds("x-4"), vec![Ident("x"), Punct('-'), Number(4)]);
+        assert_eq!(kinds("x -4"), vec![Ident("x"), Number(-4)]);
+        assert_eq!(kinds("- 4"), vec![Punct('-'), Number(4)]);
+    }
+
+    #[test]
+    fn overflow_at_the_limits() {
+        assert_eq!(all_numbers("9223372036854775807 -9223372036854775808"), Ok(vec![i64::MAX, i64::MIN]));
+        for src in ["9223372036854775808", "-9223372036854775809"] {
+            let e = all_numbers(src).unwrap_err();
+            assert_eq!(e.kind, LexErrorKind::Overflow);
+            assert_eq!((e.span.start, e.span.end), (0, src.len()));
+        }
+    }
+
+    #[test]
+    fn spans_across_lines() {
+        let spans = Lexer::new("ab 12\n  ,\r\nc")
+            .map(|t| t.unwrap().span)
+            .map(|s| (s.start, s.end, s.line, s.col))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![(0, 2, 1, 1), (3, 5, 1, 4), (8, 9, 2, 3), (11, 12, 3, 1)]);
+    }
+
+    #[test]
+    fn non_ascii_input() {
+        let mut lex = Lexer::new("a é b");
+        assert_eq!(lex.next().unwrap().unwrap().kind, TokenKind::Ident("a"));
+        let e = lex.next().unwrap().unwrap_err();
+        assert_eq!(e.kind, LexErrorKind::Unexpected('é'));
+        assert_eq!((e.span.start, e.span.end, e.span.col), (2, 4, 3));
+        // columns count characters, offsets count bytes
+        let b = lex.next().unwrap().unwrap();
+        assert_eq!((b.kind, b.span.start, b.span.col), (TokenKind::Ident("b"), 5, 5));
+        assert!(lex.next().is_none());
+    }
+
+    #[test]
+    fn expect_names_what_was_wanted() {
+        let mut lex = Lexer::new("a , 1");
+        assert_eq!(lex.expect_ident().map(|(s, _)| s), Ok("a"));
+        let e = lex.expect_punct(';').unwrap_err();
+        assert_eq!(e.kind, LexErrorKind::ExpectedPunct(';'));
+        assert_eq!(e.to_string(), "line 1, col 3: expected ';'");
+        assert!(lex.expect_punct(',').is_ok());
+        assert_eq!(lex.expect_number().map(|(n, _)| n), Ok(1));
+        assert_eq!(lex.expect_number().unwrap_err().kind, LexErrorKind::EndOfInput);
+    }
+
+    #[test]
+    fn digit_rows() {
+        let mut lex = Lexer::new("0123\n98765432109876543210\n x");
+        assert!(!lex.is_done());
+        assert_eq!(lex.digits().map(|(d, _)| d), Ok(vec![0, 1, 2, 3]));
+        let (row, span) = lex.digits().unwrap();
+        assert_eq!((row.len(), span.line, span.col), (20, 2, 1));
+        let e = lex.digits().unwrap_err();
+        assert_eq!((e.kind, e.span.line, e.span.col), (LexErrorKind::Expected("digits"), 3, 2));
+        assert_eq!(lex.expect_ident().map(|(s, _)| s), Ok("x"));
+        assert_eq!(lex.digits().unwrap_err().kind, LexErrorKind::EndOfInput);
+    }
+
+    #[test]
+    fn chunks_in_groups() {
+        assert_eq!(chunks::<_, 2>(1..=4).collect::<Vec<_>>(), vec![[1, 2], [3, 4]]);
+        assert_eq!(chunks::<_, 3>(0..0).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "2 items left over")]
+    fn chunks_with_leftovers() {
+        chunks::<_, 3>(1..=5).for_each(drop)
+    }
+}